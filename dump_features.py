@@ -0,0 +1,80 @@
+# Writes the fixtures of tests/feature_parity.rs with the Python KoiKoi-AI environment.
+# `base` is base.py of KoiKoi-AI (https://github.com/guansanghai/KoiKoi-AI), which is not
+# packaged: run the script from a checkout of that repository, with numpy installed:
+#     python dump_features.py <rust-burn-test>/tests/fixtures/features
+# The commit of the checkout is written in each fixture.
+import json
+import os
+import random
+import subprocess
+import sys
+
+import numpy as np
+
+import base
+
+out_dir = sys.argv[1] if len(sys.argv) > 1 else "tests/fixtures/features"
+n_games = 5
+os.makedirs(out_dir, exist_ok=True)
+koikoi_ai_commit = subprocess.run(["git", "rev-parse", "HEAD"], capture_output=True, text=True).stdout.strip()
+
+
+def cards(card_list):
+    return [[int(card[0]), int(card[1])] for card in card_list]
+
+
+def round_state_dict(rs):
+    return {
+        "dealer": rs.dealer - 1,
+        "turn_16": rs.turn_16,
+        "state": rs.state,
+        "hand": [cards(rs.hand[1]), cards(rs.hand[2])],
+        "pile": [cards(rs.pile[1]), cards(rs.pile[2])],
+        "field": cards(rs.field),
+        "stock": cards(rs.stock),
+        "init_board": cards(rs.log["basic"]["initBoard"]),
+        "show": cards(rs.show),
+        "koikoi": [[int(x) for x in rs.koikoi[1]], [int(x) for x in rs.koikoi[2]]],
+        "card_log": [[np.asarray(f).tolist() for _, f in rs.card_log_dict[turn].items()]
+                     for turn in range(1, 17)],
+    }
+
+
+def dump(game_state, name):
+    fixture = {
+        "koikoi_ai_commit": koikoi_ai_commit,
+        "round": game_state.round,
+        "points": [game_state.point[1], game_state.point[2]],
+        "round_state": round_state_dict(game_state.round_state),
+        "features": np.asarray(game_state.feature_tensor).tolist(),
+    }
+    with open(os.path.join(out_dir, name + ".json"), "w") as f:
+        json.dump(fixture, f)
+
+
+def random_action(rs):
+    if rs.state == "discard":
+        rs.discard(random.choice(rs.hand[rs.turn_player]))
+    elif rs.state == "discard-pick":
+        rs.discard_pick(random.choice(rs.pairing_card) if rs.wait_action else None)
+    elif rs.state == "draw":
+        rs.draw()
+    elif rs.state == "draw-pick":
+        rs.draw_pick(random.choice(rs.pairing_card) if rs.wait_action else None)
+    elif rs.state == "koikoi":
+        rs.claim_koikoi(random.choice([True, False]) if rs.wait_action else None)
+
+
+random.seed(0)
+for game in range(n_games):
+    game_state = base.KoiKoiGameState()
+    step = 0
+    while not game_state.game_over:
+        rs = game_state.round_state
+        if rs.state == "round-over":
+            game_state.new_round()
+            continue
+        if rs.wait_action and random.random() < 0.2:
+            dump(game_state, "game{}_step{:04d}".format(game, step))
+        random_action(rs)
+        step += 1
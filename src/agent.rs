@@ -0,0 +1,245 @@
+use std::path::Path;
+use burn::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::{Action, Card, GameObservation, RoundState, State};
+use crate::game_tensor::feature_batch;
+use crate::model::{DiscardModel, KoiKoiModel, KoiKoiNet, LoadError, PickModel};
+
+/// A player of Koi-Koi, who only sees the games through the observations of the turn player.
+pub trait Agent {
+    /// Chooses one of the legal actions of the turn player, the game must not be over.
+    fn act(&mut self, state: &GameObservation) -> Action;
+
+    /// Chooses an action for each of many games at once.
+    fn act_batch(&mut self, states: &[&GameObservation]) -> Vec<Action> {
+        states.iter().map(|state| self.act(state)).collect()
+    }
+}
+
+/// A choice left to the turn player, each one is taken by one of the networks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Decision {
+    Discard,
+    Pick,
+    KoiKoi,
+}
+
+impl Decision {
+    /// The decision to take in `round`, or `None` if there is at most one legal action.
+    pub fn of(round: &RoundState) -> Option<Self> {
+        if round.legal_actions().len() < 2 {
+            return None;
+        }
+        match round.state {
+            State::Discard => Some(Decision::Discard),
+            State::DiscardPick | State::DrawPick => Some(Decision::Pick),
+            State::KoiKoi => Some(Decision::KoiKoi),
+            _ => None,
+        }
+    }
+
+    /// Name of the network taking the decision, as in its checkpoint files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Decision::Discard => "discard",
+            Decision::Pick => "pick",
+            Decision::KoiKoi => "koikoi",
+        }
+    }
+
+    /// Number of logits of the network taking the decision.
+    pub fn output_len(self) -> usize {
+        match self {
+            Decision::Discard | Decision::Pick => 48,
+            Decision::KoiKoi => 2,
+        }
+    }
+
+    /// The action of the logit at `index`. For koi-koi, index 1 calls koi-koi and 0 stops.
+    pub fn action(self, index: usize) -> Action {
+        match self {
+            Decision::Discard => Action::Discard(Card::from_index(index)),
+            Decision::Pick => Action::Pick(Card::from_index(index)),
+            Decision::KoiKoi => Action::KoiKoi(index == 1),
+        }
+    }
+
+    /// The logit index of `action`, the inverse of [`Decision::action`].
+    pub fn index(action: Action) -> Option<usize> {
+        match action {
+            Action::Discard(card) | Action::Pick(card) => Some(card.index()),
+            Action::KoiKoi(is_koikoi) => Some(is_koikoi as usize),
+            Action::Draw | Action::Continue => None,
+        }
+    }
+}
+
+/// How an action is chosen from the logits of the legal actions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sampling {
+    /// Takes the best action.
+    Argmax,
+    /// Samples from the softmax of the logits divided by the temperature, which takes the best
+    /// action if it is not positive.
+    Temperature(f32),
+    /// Samples among the `k` best actions with the given temperature.
+    TopK(usize, f32),
+}
+
+impl Sampling {
+    /// Probabilities of choosing each of the `logits`.
+    pub fn probabilities(self, logits: &[f32]) -> Vec<f32> {
+        let mut order: Vec<_> = (0..logits.len()).collect();
+        order.sort_by(|&i, &j| logits[j].total_cmp(&logits[i]));
+        let (k, temperature) = match self {
+            Sampling::Temperature(temperature) if temperature > 0. => (logits.len(), temperature),
+            Sampling::TopK(k, temperature) if temperature > 0. => (k.max(1), temperature),
+            _ => (1, 1.),
+        };
+        let mut probs = vec![0.; logits.len()];
+        let Some(&best) = order.first() else {
+            return probs;
+        };
+        for &i in order.iter().take(k) {
+            probs[i] = ((logits[i] - logits[best]) / temperature).exp();
+        }
+        let total: f32 = probs.iter().sum();
+        probs.iter_mut().for_each(|p| *p /= total);
+        probs
+    }
+
+    /// Masks `logits`, the outputs of the network taking the decision, to the legal actions
+    /// and turns them into probabilities.
+    pub fn policy(self, logits: &[f32], legal_actions: Vec<Action>) -> Vec<(Action, f32)> {
+        let legal_logits: Vec<_> = legal_actions
+            .iter()
+            .map(|&action| logits[Decision::index(action).unwrap()])
+            .collect();
+        legal_actions.into_iter().zip(self.probabilities(&legal_logits)).collect()
+    }
+}
+
+/// Plays with the discard, pick and koi-koi networks, masking the illegal actions.
+#[derive(Clone)]
+pub struct NeuralAgent<B: Backend> {
+    discard_model: DiscardModel<B>,
+    pick_model: PickModel<B>,
+    koikoi_model: KoiKoiModel<B>,
+    sampling: Sampling,
+    device: B::Device,
+    rng: ChaCha8Rng,
+}
+
+impl<B: Backend> NeuralAgent<B> {
+    pub fn new(
+        discard_model: DiscardModel<B>,
+        pick_model: PickModel<B>,
+        koikoi_model: KoiKoiModel<B>,
+        sampling: Sampling,
+        device: B::Device,
+    ) -> Self {
+        Self {
+            discard_model,
+            pick_model,
+            koikoi_model,
+            sampling,
+            device,
+            rng: ChaCha8Rng::seed_from_u64(0),
+        }
+    }
+
+    /// Loads `discard_{tag}`, `pick_{tag}` and `koikoi_{tag}` from `dir`, as `.safetensors`, burn
+    /// `.mpk` or PyTorch `.pt` checkpoints. KoiKoi-AI saves them with the tags `sl` and `rl`.
+    pub fn load(dir: &Path, tag: &str, sampling: Sampling, device: B::Device) -> Result<Self, LoadError> {
+        let discard_model = load_checkpoint(dir, "discard", tag, &device)?;
+        let pick_model = load_checkpoint(dir, "pick", tag, &device)?;
+        let koikoi_model = load_checkpoint(dir, "koikoi", tag, &device)?;
+        Ok(Self::new(discard_model, pick_model, koikoi_model, sampling, device))
+    }
+
+    /// Seeds the sampling of the actions.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
+
+    /// The discard, pick and koi-koi networks.
+    pub fn into_models(self) -> (DiscardModel<B>, PickModel<B>, KoiKoiModel<B>) {
+        (self.discard_model, self.pick_model, self.koikoi_model)
+    }
+
+    /// Logits of the network taking `decision` for a batch of features.
+    pub fn logits(&self, decision: Decision, features: Tensor<B, 3>) -> Tensor<B, 2> {
+        match decision {
+            Decision::Discard => self.discard_model.forward(features),
+            Decision::Pick => self.pick_model.forward(features),
+            Decision::KoiKoi => self.koikoi_model.forward(features),
+        }
+    }
+
+    /// Probabilities of the legal actions of the turn player.
+    pub fn policy(&self, state: &GameObservation) -> Vec<(Action, f32)> {
+        self.policy_batch(&[state]).remove(0)
+    }
+
+    /// Probabilities of the legal actions in many games, each network runs once on all
+    /// the games waiting for its decision.
+    pub fn policy_batch(&self, states: &[&GameObservation]) -> Vec<Vec<(Action, f32)>> {
+        let decisions: Vec<_> = states.iter().map(|state| Decision::of(&state.round_state.round)).collect();
+        let mut policies: Vec<_> = states
+            .iter()
+            .map(|state| state.legal_actions().into_iter().map(|action| (action, 1.)).collect())
+            .collect();
+        for decision in [Decision::Discard, Decision::Pick, Decision::KoiKoi] {
+            let indices: Vec<_> = (0..states.len()).filter(|&i| decisions[i] == Some(decision)).collect();
+            if indices.is_empty() {
+                continue;
+            }
+            let batch: Vec<_> = indices.iter().map(|&i| states[i]).collect();
+            let logits = self.logits(decision, feature_batch(&batch, &self.device))
+                .into_data()
+                .convert::<f32>()
+                .to_vec::<f32>()
+                .unwrap();
+            for (logits, &i) in logits.chunks(decision.output_len()).zip(&indices) {
+                policies[i] = self.sampling.policy(logits, states[i].legal_actions());
+            }
+        }
+        policies
+    }
+}
+
+/// Loads the checkpoint `{name}_{tag}` of `dir` in the first format found.
+fn load_checkpoint<B: Backend, M: KoiKoiNet<B>>(dir: &Path, name: &str, tag: &str, device: &B::Device) -> Result<M, LoadError> {
+    let path = |extension| dir.join(format!("{name}_{tag}.{extension}"));
+    if path("safetensors").exists() {
+        M::load_safetensors(path("safetensors"), device)
+    } else if path("mpk").exists() {
+        M::load_mpk(path("mpk"), device)
+    } else if path("pt").exists() {
+        M::load_pytorch(path("pt"), device)
+    } else {
+        Err(LoadError::Read(format!("no {name}_{tag} checkpoint in {}", dir.display())))
+    }
+}
+
+pub(crate) fn sample(policy: &[(Action, f32)], rng: &mut ChaCha8Rng) -> Action {
+    let weights = WeightedIndex::new(policy.iter().map(|(_, p)| p)).expect("no legal action");
+    policy[weights.sample(rng)].0
+}
+
+impl<B: Backend> Agent for NeuralAgent<B> {
+    fn act(&mut self, state: &GameObservation) -> Action {
+        let policy = self.policy(state);
+        sample(&policy, &mut self.rng)
+    }
+
+    fn act_batch(&mut self, states: &[&GameObservation]) -> Vec<Action> {
+        self.policy_batch(states)
+            .iter()
+            .map(|policy| sample(policy, &mut self.rng))
+            .collect()
+    }
+}
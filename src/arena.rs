@@ -0,0 +1,319 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+use burn::prelude::Backend;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::agent::{Agent, NeuralAgent, Sampling};
+use crate::game::{Action, Card, CardSet, GameConfig, GameObservation, GameState, Player};
+use crate::mcts::{Ismcts, IsmctsConfig};
+use crate::model::LoadError;
+use crate::record::GameRecord;
+
+/// Plays uniformly at random among the legal actions.
+pub struct RandomAgent {
+    rng: ChaCha8Rng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: ChaCha8Rng::seed_from_u64(seed) }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn act(&mut self, state: &GameObservation) -> Action {
+        *state.legal_actions().choose(&mut self.rng).expect("no legal action")
+    }
+}
+
+/// Captures the most valuable cards it can and never calls koi-koi.
+/// The value of a card only depends on its kind, brights and animals coming first.
+pub struct GreedyAgent;
+
+fn card_value(card: Card) -> i32 {
+    4 - card.kind() as i32
+}
+
+impl Agent for GreedyAgent {
+    fn act(&mut self, state: &GameObservation) -> Action {
+        let field = state.round_state.round.field;
+        let gain = |action: &Action| match *action {
+            Action::Discard(card) => {
+                let best_pair = (field & CardSet::month(card.month())).iter().map(card_value).max();
+                match best_pair {
+                    Some(value) => card_value(card) + value,
+                    None => -card_value(card),
+                }
+            }
+            Action::Pick(card) => card_value(card),
+            Action::KoiKoi(is_koikoi) => !is_koikoi as i32,
+            Action::Draw | Action::Continue => 0,
+        };
+        state.legal_actions().into_iter().max_by_key(gain).expect("no legal action")
+    }
+}
+
+/// An agent given by name: `random`, `greedy` or `<dir>[:<tag>]` for the networks
+/// saved in `dir`, see [`NeuralAgent::load`]. The tag is `sl` by default.
+/// `ismcts[:<iterations>][@<dir>[:<tag>]]` searches with [`Ismcts`], the networks giving the priors.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AgentSpec {
+    Random,
+    Greedy,
+    Checkpoint { dir: PathBuf, tag: String },
+    Ismcts { iterations: usize, prior: Option<(PathBuf, String)> },
+}
+
+impl AgentSpec {
+    pub fn build<B: Backend>(&self, sampling: Sampling, seed: u64, device: &B::Device) -> Result<Box<dyn Agent>, LoadError> {
+        Ok(match self {
+            AgentSpec::Random => Box::new(RandomAgent::new(seed)),
+            AgentSpec::Greedy => Box::new(GreedyAgent),
+            AgentSpec::Checkpoint { dir, tag } =>
+                Box::new(NeuralAgent::<B>::load(dir, tag, sampling, device.clone())?.with_seed(seed)),
+            AgentSpec::Ismcts { iterations, prior } => {
+                let config = IsmctsConfig { iterations: *iterations, ..IsmctsConfig::default() };
+                let mut search = Ismcts::new(config).with_seed(seed);
+                if let Some((dir, tag)) = prior {
+                    let prior = NeuralAgent::<B>::load(dir, tag, Sampling::Temperature(1.), device.clone())?;
+                    search = search.with_prior(Box::new(prior));
+                }
+                Box::new(search)
+            }
+        })
+    }
+}
+
+/// Error raised by a malformed `ismcts` [`AgentSpec`].
+#[derive(Debug, PartialEq)]
+pub enum SpecError {
+    /// The number of determinizations is not a positive integer.
+    Iterations(String),
+    /// Nothing follows the `@` of the networks giving the priors.
+    Prior(String),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecError::Iterations(iterations) =>
+                write!(f, "invalid number of determinizations {iterations:?}, expected a positive integer"),
+            SpecError::Prior(spec) => write!(f, "missing networks after the @ of {spec}"),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl FromStr for AgentSpec {
+    type Err = SpecError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Ok(match spec {
+            "random" => AgentSpec::Random,
+            "greedy" => AgentSpec::Greedy,
+            _ => match ismcts(spec) {
+                Some(spec) => spec?,
+                None => {
+                    let (dir, tag) = checkpoint(spec);
+                    AgentSpec::Checkpoint { dir, tag }
+                }
+            },
+        })
+    }
+}
+
+fn checkpoint(spec: &str) -> (PathBuf, String) {
+    let (dir, tag) = spec.rsplit_once(':').unwrap_or((spec, "sl"));
+    (dir.into(), tag.to_string())
+}
+
+/// Parses `ismcts[:<n>][@<dir>[:<tag>]]`, `None` for the other specs.
+fn ismcts(spec: &str) -> Option<Result<AgentSpec, SpecError>> {
+    let (search, prior) = match spec.split_once('@') {
+        Some((search, prior)) => (search, Some(prior)),
+        None => (spec, None),
+    };
+    let iterations = match search.strip_prefix("ismcts")? {
+        "" => IsmctsConfig::default().iterations,
+        // the other names starting with ismcts are checkpoint directories
+        iterations => {
+            let iterations = iterations.strip_prefix(':')?;
+            match iterations.parse() {
+                Ok(iterations) if iterations > 0 => iterations,
+                _ => return Some(Err(SpecError::Iterations(iterations.to_string()))),
+            }
+        }
+    };
+    let prior = match prior {
+        Some("") => return Some(Err(SpecError::Prior(spec.to_string()))),
+        prior => prior.map(checkpoint),
+    };
+    Some(Ok(AgentSpec::Ismcts { iterations, prior }))
+}
+
+impl fmt::Display for AgentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgentSpec::Random => write!(f, "random"),
+            AgentSpec::Greedy => write!(f, "greedy"),
+            AgentSpec::Checkpoint { dir, tag } => write!(f, "{}:{tag}", dir.display()),
+            AgentSpec::Ismcts { iterations, prior: None } => write!(f, "ismcts:{iterations}"),
+            AgentSpec::Ismcts { iterations, prior: Some((dir, tag)) } =>
+                write!(f, "ismcts:{iterations}@{}:{tag}", dir.display()),
+        }
+    }
+}
+
+/// Plays matches between two agents, swapping their seats and the first dealer.
+/// Matches are played two by two on the same deals, one for each seat.
+#[derive(Clone, Debug)]
+pub struct Arena {
+    pub matches: usize,
+    /// Settings of the matches, the seed and the first dealer change between matches.
+    pub config: GameConfig,
+}
+
+impl Arena {
+    pub fn new(matches: usize, config: GameConfig) -> Self {
+        Self { matches, config }
+    }
+
+    /// Seat of the first agent in the match `index`.
+    fn seat(index: usize) -> Player {
+        Player::BOTH[index % 2]
+    }
+
+    fn game_config(&self, index: usize) -> GameConfig {
+        GameConfig {
+            init_dealer: Player::BOTH[(self.config.init_dealer.index() + index / 2) % 2],
+            seed: self.config.seed + (index / 2) as u64,
+            ..self.config.clone()
+        }
+    }
+
+    /// Plays all the matches at once, each agent acts in one batch on the games waiting for it.
+    pub fn play(&self, first: &mut dyn Agent, second: &mut dyn Agent) -> ArenaReport {
+        self.play_recorded(first, second).0
+    }
+
+    /// Plays the matches as [`Arena::play`] and also returns their records.
+    pub fn play_recorded(&self, first: &mut dyn Agent, second: &mut dyn Agent) -> (ArenaReport, Vec<GameRecord>) {
+        let mut agents: [&mut dyn Agent; 2] = [first, second];
+        let mut games: Vec<_> = (0..self.matches).map(|i| GameState::new(self.game_config(i))).collect();
+        let mut records: Vec<_> = (0..self.matches).map(|i| GameRecord::new(&self.game_config(i))).collect();
+        loop {
+            let mut waiting = [vec!(), vec!()];
+            for (i, game) in games.iter().enumerate().filter(|(_, game)| !game.game_over) {
+                let agent = (game.round_state.turn_player() != Self::seat(i)) as usize;
+                waiting[agent].push(i);
+            }
+            if waiting.iter().all(Vec::is_empty) {
+                break;
+            }
+            for (agent, indices) in agents.iter_mut().zip(&waiting) {
+                if indices.is_empty() {
+                    continue;
+                }
+                let observations: Vec<_> = indices
+                    .iter()
+                    .map(|&i| games[i].observation(games[i].round_state.turn_player()))
+                    .collect();
+                let actions = agent.act_batch(&observations.iter().collect::<Vec<_>>());
+                for (&i, action) in indices.iter().zip(actions) {
+                    records[i].push(&games[i], action);
+                    games[i].step(action).expect("agents must play legal actions");
+                }
+            }
+        }
+
+        let mut report = ArenaReport::default();
+        for (i, game) in games.iter().enumerate() {
+            let seat = Self::seat(i);
+            match game.winner {
+                Some(winner) if winner == seat => report.wins += 1,
+                Some(_) => report.losses += 1,
+                None => report.draws += 1,
+            }
+            report.point_diffs.push(game.points[seat] - game.points[seat.opponent()]);
+        }
+        (report, records)
+    }
+}
+
+/// Results of the first agent of an [`Arena`].
+#[derive(Clone, Debug, Default)]
+pub struct ArenaReport {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Final points of the first agent minus the ones of the second, for each match.
+    pub point_diffs: Vec<i32>,
+}
+
+impl ArenaReport {
+    pub fn matches(&self) -> usize {
+        self.point_diffs.len()
+    }
+
+    /// Win rate, a draw counts as half a win.
+    pub fn win_rate(&self) -> Estimate {
+        let scores = self.point_diffs.iter().map(|&diff| match diff.signum() {
+            1 => 1.,
+            0 => 0.5,
+            _ => 0.,
+        });
+        Estimate::of(scores)
+    }
+
+    pub fn point_diff(&self) -> Estimate {
+        Estimate::of(self.point_diffs.iter().map(|&diff| diff as f64))
+    }
+}
+
+impl fmt::Display for ArenaReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "matches: {}", self.matches())?;
+        writeln!(f, "wins / draws / losses: {} / {} / {}", self.wins, self.draws, self.losses)?;
+        writeln!(f, "win rate: {:.3}", self.win_rate())?;
+        write!(f, "point difference: {:.2}", self.point_diff())
+    }
+}
+
+/// A sample mean with the half width of its 95% confidence interval.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Estimate {
+    pub mean: f64,
+    pub margin: f64,
+}
+
+impl Estimate {
+    /// Normal approximation of the mean of `samples`.
+    pub fn of(samples: impl Iterator<Item = f64>) -> Self {
+        let samples: Vec<_> = samples.collect();
+        let n = samples.len() as f64;
+        if samples.is_empty() {
+            return Self { mean: f64::NAN, margin: f64::NAN };
+        }
+        let mean = samples.iter().sum::<f64>() / n;
+        if samples.len() < 2 {
+            return Self { mean, margin: f64::INFINITY };
+        }
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.);
+        Self { mean, margin: 1.96 * (variance / n).sqrt() }
+    }
+
+    pub fn interval(&self) -> (f64, f64) {
+        (self.mean - self.margin, self.mean + self.margin)
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (low, high) = self.interval();
+        match f.precision() {
+            Some(p) => write!(f, "{:.p$} (95% CI {:.p$} to {:.p$})", self.mean, low, high),
+            None => write!(f, "{} (95% CI {} to {})", self.mean, low, high),
+        }
+    }
+}
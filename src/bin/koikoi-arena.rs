@@ -0,0 +1,88 @@
+use std::{env, path::PathBuf};
+use burn::backend::candle::{Candle, CandleDevice};
+use rust_burn_test::agent::{Agent, Sampling};
+use rust_burn_test::arena::{AgentSpec, Arena};
+use rust_burn_test::cli::{exit_on_error, Cli, RulesOptions};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::rules::RuleSet;
+use rust_burn_test::record::append_records;
+
+type B = Candle<f32, i64>;
+
+const USAGE: &str = "\
+usage: koikoi-arena <agent> <agent> [options]
+
+agents:
+    random            plays uniformly at random
+    greedy            captures the most valuable cards, never calls koi-koi
+    <dir>[:<tag>]     the networks {discard,pick,koikoi}_<tag>.{safetensors,mpk,pt} in <dir>, tag sl by default
+    ismcts[:<n>][@<dir>[:<tag>]]
+                      searches <n> determinizations per decision, 500 by default, with the networks as priors
+
+options:
+    --matches <n>        number of matches, 1000 by default
+    --rules <file>       house rules as TOML, or JSON for other extensions, the KoiKoi-AI rules by default
+    --rounds <n>         rounds per match, 8 or the ones of the rules by default
+    --seed <n>           seed of the deals and of the agents, 0 by default
+    --temperature <t>    samples the actions of the networks instead of playing the best one
+    --record <file>      appends the matches to a JSON lines record file";
+
+const CLI: Cli = Cli::new(USAGE);
+
+struct Args {
+    agents: Vec<String>,
+    matches: usize,
+    config: GameConfig,
+    sampling: Sampling,
+    record: Option<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        agents: vec!(),
+        matches: 1000,
+        config: GameConfig::default(),
+        sampling: Sampling::Argmax,
+        record: None,
+    };
+    let mut rules = RulesOptions::default();
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--matches" => args.matches = CLI.value(&arg, argv.next()),
+            "--rules" | "--rounds" => rules.set(&CLI, &arg, argv.next()),
+            "--seed" => args.config.seed = CLI.value(&arg, argv.next()),
+            "--temperature" => args.sampling = Sampling::Temperature(CLI.value(&arg, argv.next())),
+            "--record" => args.record = Some(CLI.value(&arg, argv.next())),
+            "-h" | "--help" => CLI.help(),
+            _ if arg.starts_with("--") => CLI.unknown(&arg),
+            _ => args.agents.push(arg),
+        }
+    }
+    if args.agents.len() != 2 {
+        CLI.fail("expected two agents");
+    }
+    args.config.rules = rules.rules(RuleSet::default());
+    args
+}
+
+fn agent(spec: &str, args: &Args, seed: u64, device: &CandleDevice) -> Box<dyn Agent> {
+    let agent_spec = spec.parse::<AgentSpec>().unwrap_or_else(|err| CLI.fail(&format!("{spec}: {err}")));
+    exit_on_error(agent_spec.build::<B>(args.sampling, seed, device), spec)
+}
+
+fn main() {
+    let args = parse_args();
+    let device = CandleDevice::default();
+    let seed = args.config.seed;
+    let mut first = agent(&args.agents[0], &args, seed, &device);
+    let mut second = agent(&args.agents[1], &args, seed + 1, &device);
+
+    let arena = Arena::new(args.matches, args.config.clone());
+    let (report, records) = arena.play_recorded(first.as_mut(), second.as_mut());
+    if let Some(path) = &args.record {
+        exit_on_error(append_records(path, &records), &path.display().to_string());
+    }
+    println!("{} against {}", args.agents[0], args.agents[1]);
+    println!("{report}");
+}
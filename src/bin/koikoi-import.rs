@@ -0,0 +1,53 @@
+use std::{env, path::PathBuf, process};
+use rust_burn_test::cli::{exit_on_error, Cli, RulesOptions};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::python_log::import_file;
+use rust_burn_test::record::append_records;
+use rust_burn_test::rules::RuleSet;
+
+const USAGE: &str = "\
+usage: koikoi-import <log>... --out <file> [options]
+
+Imports the game logs of the Python KoiKoi-AI environment and appends them to a record file.
+The logs diverging from the engine are reported and skipped.
+
+options:
+    --out <file>       JSON lines record file
+    --rules <file>     house rules as TOML, or JSON for other extensions, the KoiKoi-AI rules by default
+    --rounds <n>       rounds per match, 8 or the ones of the rules by default
+    --points <n>       initial points of the players, 30 by default";
+
+const CLI: Cli = Cli::new(USAGE);
+
+fn main() {
+    let mut logs: Vec<PathBuf> = vec!();
+    let mut out: Option<PathBuf> = None;
+    let mut config = GameConfig::default();
+    let mut rules = RulesOptions::default();
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--out" => out = Some(CLI.value(&arg, argv.next())),
+            "--rules" | "--rounds" => rules.set(&CLI, &arg, argv.next()),
+            "--points" => config.init_point = CLI.value(&arg, argv.next()),
+            "-h" | "--help" => CLI.help(),
+            _ if arg.starts_with("--") => CLI.unknown(&arg),
+            _ => logs.push(arg.into()),
+        }
+    }
+    let Some(out) = out else { CLI.fail("missing --out") };
+    config.rules = rules.rules(RuleSet::default());
+
+    let mut records = vec!();
+    for path in &logs {
+        match import_file(path, &config) {
+            Ok(record) => records.push(record),
+            Err(err) => eprintln!("{}: {err}", path.display()),
+        }
+    }
+    exit_on_error(append_records(&out, &records), &out.display().to_string());
+    println!("imported {} of {} logs", records.len(), logs.len());
+    if records.len() < logs.len() {
+        process::exit(1)
+    }
+}
@@ -0,0 +1,122 @@
+use std::{env, path::PathBuf, process};
+use burn::backend::candle::{Candle, CandleDevice};
+use rust_burn_test::agent::{Agent, Sampling};
+use rust_burn_test::arena::{AgentSpec, Arena};
+use rust_burn_test::cli::{exit_on_error, Cli, RulesOptions};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::rating::RatingPool;
+
+type B = Candle<f32, i64>;
+
+const USAGE: &str = "\
+usage: koikoi-ladder [--pool <file>] <command>
+
+commands:
+    show                  prints the leaderboard, the default command
+    add <name> <agent>    adds an agent with the initial rating
+    remove <name>         removes an agent
+    play [options]        plays a round robin between all the agents and updates their ratings
+
+agents:
+    random, greedy or <dir>[:<tag>] for the networks {discard,pick,koikoi}_<tag>.{safetensors,mpk,pt} in <dir>
+    ismcts[:<n>][@<dir>[:<tag>]] for a tree search of <n> determinizations per decision
+
+options:
+    --pool <file>        ratings of the agents, ratings.json by default
+    --matches <n>        matches between each pair of agents, 200 by default
+    --rules <file>       house rules as TOML, or JSON for other extensions, the rules of the pool by default
+    --rounds <n>         rounds per match, the ones of the rules by default
+    --seed <n>           seed of the deals and of the agents, 0 by default
+    --temperature <t>    samples the actions of the networks instead of playing the best one";
+
+const CLI: Cli = Cli::new(USAGE);
+
+struct Args {
+    pool: PathBuf,
+    command: Vec<String>,
+    matches: usize,
+    /// House rules given on the command line, the pool keeps its rules otherwise.
+    rules: RulesOptions,
+    config: GameConfig,
+    sampling: Sampling,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        pool: PathBuf::from("ratings.json"),
+        command: vec!(),
+        matches: 200,
+        rules: RulesOptions::default(),
+        config: GameConfig::default(),
+        sampling: Sampling::Argmax,
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--pool" => args.pool = CLI.value(&arg, argv.next()),
+            "--matches" => args.matches = CLI.value(&arg, argv.next()),
+            "--rules" | "--rounds" => args.rules.set(&CLI, &arg, argv.next()),
+            "--seed" => args.config.seed = CLI.value(&arg, argv.next()),
+            "--temperature" => args.sampling = Sampling::Temperature(CLI.value(&arg, argv.next())),
+            "-h" | "--help" => CLI.help(),
+            _ if arg.starts_with("--") => CLI.unknown(&arg),
+            _ => args.command.push(arg),
+        }
+    }
+    args
+}
+
+fn play(pool: &mut RatingPool, args: &Args) {
+    let rules = args.rules.rules(pool.rules);
+    if !pool.set_rules(rules) {
+        eprintln!("{}: the ratings of the pool were played under other rules", args.pool.display());
+        process::exit(1)
+    }
+    let config = GameConfig { rules, ..args.config.clone() };
+    let device = CandleDevice::default();
+    let mut agents: Vec<Box<dyn Agent>> = pool.entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let spec = exit_on_error(entry.agent.parse::<AgentSpec>(), &entry.name);
+            exit_on_error(spec.build::<B>(args.sampling, args.config.seed + i as u64, &device), &entry.name)
+        })
+        .collect();
+    let arena = Arena::new(args.matches, config);
+    for (i, j) in pool.round_robin() {
+        let (left, right) = agents.split_at_mut(j);
+        let report = arena.play(left[i].as_mut(), right[0].as_mut());
+        println!(
+            "{} against {}: win rate {:.3}",
+            pool.entries[i].name, pool.entries[j].name, report.win_rate(),
+        );
+        pool.record(i, j, &report);
+        exit_on_error(pool.save(&args.pool), &args.pool.display().to_string());
+    }
+}
+
+fn main() {
+    let args = parse_args();
+    let mut pool = exit_on_error(RatingPool::load(&args.pool), &args.pool.display().to_string());
+    let command: Vec<_> = args.command.iter().map(String::as_str).collect();
+    match command[..] {
+        [] | ["show"] => {}
+        ["add", name, agent] => {
+            if let Err(err) = agent.parse::<AgentSpec>() {
+                CLI.fail(&format!("{agent}: {err}"));
+            }
+            if !pool.add(name, agent) {
+                CLI.fail(&format!("{name} is already in the pool"));
+            }
+        }
+        ["remove", name] => {
+            if pool.remove(name).is_none() {
+                CLI.fail(&format!("{name} is not in the pool"));
+            }
+        }
+        ["play"] => play(&mut pool, &args),
+        _ => CLI.fail("invalid command"),
+    }
+    exit_on_error(pool.save(&args.pool), &args.pool.display().to_string());
+    print!("{pool}");
+}
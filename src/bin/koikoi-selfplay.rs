@@ -0,0 +1,126 @@
+use std::{env, fs, io::Write, path::{Path, PathBuf}};
+use burn::backend::{candle::{Candle, CandleDevice}, Autodiff};
+use burn::optim::AdamConfig;
+use rust_burn_test::agent::{NeuralAgent, Sampling};
+use rust_burn_test::arena::AgentSpec;
+use rust_burn_test::cli::{exit_on_error, Cli, RulesOptions};
+use rust_burn_test::model::KoiKoiNetConfig;
+use rust_burn_test::reinforce::{IterationLog, SelfPlay, SelfPlayConfig};
+use rust_burn_test::rules::RuleSet;
+
+type B = Autodiff<Candle<f32, i64>>;
+
+const USAGE: &str = "\
+usage: koikoi-selfplay [options]
+
+Fine-tunes the networks by REINFORCE on matches they play against themselves.
+
+options:
+    --init <dir>[:<tag>]  networks to start from, models:sl by default
+    --reference <agent>   random, greedy or <dir>[:<tag>], the networks evaluated against, the initial ones by default
+    --iterations <n>      100 by default
+    --matches <n>         matches played at each iteration, 8 by default
+    --rules <file>        house rules as TOML, or JSON for other extensions, the KoiKoi-AI rules by default
+    --rounds <n>          rounds per match, 8 or the ones of the rules by default
+    --temperature <t>     temperature of the sampling of the actions, 1 by default
+    --discount <d>        weight of the points of the next round in the rewards, 0 by default
+    --entropy <c>         weight of the entropy bonus, 0.01 by default
+    --batch-size <n>      32 by default
+    --lr <rate>           learning rate, 1e-5 by default
+    --eval-every <n>      iterations between evaluations, 10 by default
+    --eval-matches <n>    matches of an evaluation, 200 by default
+    --seed <n>            0 by default
+    --out <dir>           directory of the trained networks, models by default
+    --tag <tag>           the networks are saved as <out>/{discard,pick,koikoi}_<tag>.mpk at each evaluation, rl by default
+    --log <file>          CSV learning curves, <out>/selfplay_<tag>.csv by default";
+
+const CLI: Cli = Cli::new(USAGE);
+
+struct Args {
+    init: String,
+    reference: Option<String>,
+    iterations: usize,
+    eval_every: usize,
+    eval_matches: usize,
+    out: PathBuf,
+    tag: String,
+    log: Option<PathBuf>,
+    config: SelfPlayConfig,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        init: "models:sl".to_string(),
+        reference: None,
+        iterations: 100,
+        eval_every: 10,
+        eval_matches: 200,
+        out: PathBuf::from("models"),
+        tag: "rl".to_string(),
+        log: None,
+        config: SelfPlayConfig::new(AdamConfig::new()),
+    };
+    let mut rules = RulesOptions::default();
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--init" => args.init = CLI.value(&arg, argv.next()),
+            "--reference" => args.reference = Some(CLI.value(&arg, argv.next())),
+            "--iterations" => args.iterations = CLI.value(&arg, argv.next()),
+            "--matches" => args.config.matches = CLI.value(&arg, argv.next()),
+            "--rules" | "--rounds" => rules.set(&CLI, &arg, argv.next()),
+            "--temperature" => args.config.temperature = CLI.value(&arg, argv.next()),
+            "--discount" => args.config.discount = CLI.value(&arg, argv.next()),
+            "--entropy" => args.config.entropy_coef = CLI.value(&arg, argv.next()),
+            "--batch-size" => args.config.batch_size = CLI.value(&arg, argv.next()),
+            "--lr" => args.config.learning_rate = CLI.value(&arg, argv.next()),
+            "--eval-every" => args.eval_every = CLI.value::<usize>(&arg, argv.next()).max(1),
+            "--eval-matches" => args.eval_matches = CLI.value(&arg, argv.next()),
+            "--seed" => args.config.seed = CLI.value(&arg, argv.next()),
+            "--out" => args.out = CLI.value(&arg, argv.next()),
+            "--tag" => args.tag = CLI.value(&arg, argv.next()),
+            "--log" => args.log = Some(CLI.value(&arg, argv.next())),
+            "-h" | "--help" => CLI.help(),
+            _ => CLI.unknown(&arg),
+        }
+    }
+    args.config.rules = rules.rules(RuleSet::default());
+    args
+}
+
+fn main() {
+    let args = parse_args();
+    let device = CandleDevice::default();
+    let Ok(AgentSpec::Checkpoint { dir, tag }) = args.init.parse::<AgentSpec>() else {
+        CLI.fail(&format!("{} is not a checkpoint set", args.init))
+    };
+    let agent = exit_on_error(NeuralAgent::<B>::load(&dir, &tag, Sampling::Argmax, device), &args.init);
+    let net = exit_on_error(KoiKoiNetConfig::for_checkpoint(&dir.join(format!("discard_{tag}.mpk"))), &args.init);
+    let reference_spec = args.reference.as_deref().unwrap_or(&args.init);
+    let reference = reference_spec.parse::<AgentSpec>().unwrap_or_else(|err| CLI.fail(&format!("{reference_spec}: {err}")));
+    let mut reference = exit_on_error(
+        reference.build::<Candle<f32, i64>>(Sampling::Argmax, args.config.seed, &device),
+        reference_spec,
+    );
+
+    exit_on_error(fs::create_dir_all(&args.out), &args.out.display().to_string());
+    let log_path = args.log.clone().unwrap_or_else(|| args.out.join(format!("selfplay_{}.csv", args.tag)));
+    let mut log = exit_on_error(fs::File::create(&log_path), &log_path.display().to_string());
+    let mut write_row = |row: &str, path: &Path| {
+        exit_on_error(writeln!(log, "{row}").and_then(|_| log.flush()), &path.display().to_string())
+    };
+    write_row(IterationLog::CSV_HEADER, &log_path);
+
+    let mut trainer = SelfPlay::new(agent, net, args.config.clone(), device);
+    for iteration in 1..=args.iterations {
+        let mut stats = trainer.iterate();
+        if iteration % args.eval_every == 0 || iteration == args.iterations {
+            let report = trainer.evaluate(reference.as_mut(), args.eval_matches);
+            println!("iteration {iteration} against {reference_spec}: {report}");
+            stats.evaluation = Some(report);
+            exit_on_error(trainer.save(&args.out, &args.tag), &args.out.display().to_string());
+        }
+        write_row(&stats.csv_row(), &log_path);
+    }
+    println!("saved {}/{{discard,pick,koikoi}}_{}.mpk", args.out.display(), args.tag);
+}
@@ -0,0 +1,141 @@
+use std::{env, path::PathBuf};
+use burn::backend::{candle::{Candle, CandleDevice}, Autodiff};
+use burn::optim::AdamConfig;
+use rust_burn_test::agent::{Decision, Sampling};
+use rust_burn_test::arena::{AgentSpec, Arena};
+use rust_burn_test::cli::{exit_on_error, Cli, RulesOptions};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::model::KoiKoiNetConfig;
+use rust_burn_test::record::{append_records, read_records, GameRecord};
+use rust_burn_test::rules::RuleSet;
+use rust_burn_test::train::{train, DecisionDataset, TrainingConfig};
+
+type B = Autodiff<Candle<f32, i64>>;
+
+const USAGE: &str = "\
+usage: koikoi-train [options]
+
+Trains the networks to imitate the recorded matches, or an expert agent playing against itself.
+
+options:
+    --records <file>      a JSON lines file of game records, can be repeated
+    --expert <agent>      random, greedy or <dir>[:<tag>] for a checkpoint set, greedy by default
+    --save-records <file> appends the matches of the expert to a record file
+    --decision <name>     discard, pick, koikoi or all, all by default
+    --matches <n>         matches played by the expert when there is no record, 1000 by default
+    --rules <file>        house rules of the matches of the expert as TOML, or JSON for other extensions,
+                          the KoiKoi-AI rules by default
+    --rounds <n>          rounds per match of the expert, 8 or the ones of the rules by default
+    --valid <fraction>    fraction of the matches kept for validation, 0.1 by default
+    --epochs <n>          10 by default
+    --batch-size <n>      64 by default
+    --lr <rate>           learning rate, 1e-4 by default
+    --workers <n>         threads loading the positions, 4 by default
+    --seed <n>            seed of the matches and of the training, 0 by default
+    --out <dir>           directory of the trained networks, models by default
+    --tag <tag>           the networks are saved as <out>/{discard,pick,koikoi}_<tag>.mpk, sl by default";
+
+const CLI: Cli = Cli::new(USAGE);
+
+struct Args {
+    records: Vec<PathBuf>,
+    expert: String,
+    save_records: Option<PathBuf>,
+    decisions: Vec<Decision>,
+    matches: usize,
+    rules: RuleSet,
+    valid: f64,
+    seed: u64,
+    out: PathBuf,
+    tag: String,
+    training: TrainingConfig,
+}
+
+fn parse_decisions(name: &str) -> Vec<Decision> {
+    match name {
+        "discard" => vec![Decision::Discard],
+        "pick" => vec![Decision::Pick],
+        "koikoi" => vec![Decision::KoiKoi],
+        "all" => vec![Decision::Discard, Decision::Pick, Decision::KoiKoi],
+        _ => CLI.fail(&format!("unknown decision {name}")),
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        records: vec!(),
+        expert: "greedy".to_string(),
+        save_records: None,
+        decisions: parse_decisions("all"),
+        matches: 1000,
+        rules: RuleSet::default(),
+        valid: 0.1,
+        seed: 0,
+        out: PathBuf::from("models"),
+        tag: "sl".to_string(),
+        training: TrainingConfig::new(KoiKoiNetConfig::new(), AdamConfig::new()),
+    };
+    let mut rules = RulesOptions::default();
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--records" => args.records.push(CLI.value(&arg, argv.next())),
+            "--expert" => args.expert = CLI.value(&arg, argv.next()),
+            "--save-records" => args.save_records = Some(CLI.value(&arg, argv.next())),
+            "--decision" => args.decisions = parse_decisions(&CLI.value::<String>(&arg, argv.next())),
+            "--matches" => args.matches = CLI.value(&arg, argv.next()),
+            "--rules" | "--rounds" => rules.set(&CLI, &arg, argv.next()),
+            "--valid" => args.valid = CLI.value(&arg, argv.next()),
+            "--epochs" => args.training.num_epochs = CLI.value(&arg, argv.next()),
+            "--batch-size" => args.training.batch_size = CLI.value(&arg, argv.next()),
+            "--lr" => args.training.learning_rate = CLI.value(&arg, argv.next()),
+            "--workers" => args.training.num_workers = CLI.value(&arg, argv.next()),
+            "--seed" => args.seed = CLI.value(&arg, argv.next()),
+            "--out" => args.out = CLI.value(&arg, argv.next()),
+            "--tag" => args.tag = CLI.value(&arg, argv.next()),
+            "-h" | "--help" => CLI.help(),
+            _ => CLI.unknown(&arg),
+        }
+    }
+    args.rules = rules.rules(RuleSet::default());
+    args.training.seed = args.seed;
+    args
+}
+
+fn expert_records(args: &Args, device: &CandleDevice) -> Vec<GameRecord> {
+    let expert = args.expert.parse::<AgentSpec>().unwrap_or_else(|err| CLI.fail(&format!("{}: {err}", args.expert)));
+    let mut first = exit_on_error(expert.build::<Candle<f32, i64>>(Sampling::Argmax, args.seed, device), &args.expert);
+    let mut second = exit_on_error(expert.build::<Candle<f32, i64>>(Sampling::Argmax, args.seed + 1, device), &args.expert);
+    let config = GameConfig { rules: args.rules, seed: args.seed, ..GameConfig::default() };
+    println!("playing {} matches of {}", args.matches, args.expert);
+    let (_, records) = Arena::new(args.matches, config).play_recorded(first.as_mut(), second.as_mut());
+    if let Some(path) = &args.save_records {
+        exit_on_error(append_records(path, &records), &path.display().to_string());
+    }
+    records
+}
+
+fn main() {
+    let args = parse_args();
+    let device = CandleDevice::default();
+    let mut records = if args.records.is_empty() {
+        expert_records(&args, &device)
+    } else {
+        args.records
+            .iter()
+            .flat_map(|path| exit_on_error(read_records(path), &path.display().to_string()))
+            .collect()
+    };
+    // the matches of the arena on the same deals stay in the same split
+    let valid_matches = ((records.len() as f64 * args.valid) as usize).next_multiple_of(2).min(records.len());
+    let valid = records.split_off(records.len() - valid_matches);
+
+    exit_on_error(std::fs::create_dir_all(&args.out), &args.out.display().to_string());
+    for &decision in &args.decisions {
+        let context = format!("{decision:?}");
+        let train_set = exit_on_error(DecisionDataset::new(&records, decision), &context);
+        let valid_set = exit_on_error(DecisionDataset::new(&valid, decision), &context);
+        let path = exit_on_error(train::<B>(&args.out, &args.tag, &args.training, train_set, valid_set, device), &context);
+        println!("saved {}", path.display());
+    }
+}
@@ -0,0 +1,78 @@
+//! Parsing of the command lines of the binaries.
+
+use std::{fmt::Display, path::PathBuf, process, str::FromStr};
+
+use crate::rules::RuleSet;
+
+/// The command line of a binary, whose usage is printed with the errors in its arguments.
+pub struct Cli {
+    usage: &'static str,
+}
+
+impl Cli {
+    pub const fn new(usage: &'static str) -> Self {
+        Self { usage }
+    }
+
+    /// Prints `message` and the usage, then exits with the status 2.
+    pub fn fail(&self, message: &str) -> ! {
+        eprintln!("{message}\n\n{}", self.usage);
+        process::exit(2)
+    }
+
+    /// Prints the usage for `-h` and `--help`, then exits.
+    pub fn help(&self) -> ! {
+        println!("{}", self.usage);
+        process::exit(0)
+    }
+
+    /// Fails on an argument the binary does not know.
+    pub fn unknown(&self, arg: &str) -> ! {
+        self.fail(&format!("unknown argument {arg}"))
+    }
+
+    /// Parses `value`, the argument following the option `name`.
+    pub fn value<T: FromStr>(&self, name: &str, value: Option<String>) -> T {
+        let value = value.unwrap_or_else(|| self.fail(&format!("missing value for {name}")));
+        value.parse().unwrap_or_else(|_| self.fail(&format!("invalid value for {name}: {value}")))
+    }
+}
+
+/// Prints the error of `result` after `context` and exits with the status 1.
+pub fn exit_on_error<T, E: Display>(result: Result<T, E>, context: &str) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{context}: {err}");
+        process::exit(1)
+    })
+}
+
+/// The `--rules <file>` and `--rounds <n>` options.
+#[derive(Default)]
+pub struct RulesOptions {
+    pub rules: Option<PathBuf>,
+    pub rounds: Option<usize>,
+}
+
+impl RulesOptions {
+    /// Reads the value of the option `name`, `--rules` or `--rounds`.
+    pub fn set(&mut self, cli: &Cli, name: &str, value: Option<String>) {
+        match name {
+            "--rules" => self.rules = Some(cli.value(name, value)),
+            "--rounds" => self.rounds = Some(cli.value(name, value)),
+            _ => cli.unknown(name),
+        }
+    }
+
+    /// The rules of the `--rules` file, or `default`, with the rounds of `--rounds`.
+    /// Exits if the file is invalid.
+    pub fn rules(&self, default: RuleSet) -> RuleSet {
+        let mut rules = match &self.rules {
+            Some(path) => exit_on_error(RuleSet::load(path), &path.display().to_string()),
+            None => default,
+        };
+        if let Some(rounds) = self.rounds {
+            rules.round_total = rounds;
+        }
+        rules
+    }
+}
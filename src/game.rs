@@ -1,11 +1,380 @@
-use std::collections::HashSet;
-use lazy_static::lazy_static;
+use std::{cmp::Ordering, fmt};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Index, IndexMut, Not, Sub, SubAssign};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
-pub type Card = (u8, u8);
+use crate::rules::{KoiKoiBonus, RuleSet};
 
-#[derive(PartialEq, Eq, Debug)]
+/// One of the 48 cards, ordered by month then by rank, the position of the card in its month.
+/// Cards are serialized as `[month, rank]` pairs.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "(u8, u8)", try_from = "(u8, u8)")]
+pub struct Card(u8);
+
+/// The kind of a card, the sake cup is an animal that also counts as a chaff.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CardKind {
+    Bright,
+    Animal,
+    Ribbon,
+    Chaff,
+}
+
+use CardKind::{Animal, Bright, Chaff, Ribbon};
+
+const KINDS: [[CardKind; 4]; 12] = [
+    [Bright, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Bright, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Bright, Animal, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Animal, Ribbon, Chaff, Chaff],
+    [Bright, Animal, Ribbon, Chaff],
+    [Bright, Chaff, Chaff, Chaff],
+];
+
+const NAMES: [[&str; 4]; 12] = [
+    ["Crane and Sun", "Pine Poetry Ribbon", "Pine", "Pine"],
+    ["Bush Warbler", "Plum Poetry Ribbon", "Plum", "Plum"],
+    ["Camp Curtain", "Cherry Poetry Ribbon", "Cherry", "Cherry"],
+    ["Cuckoo", "Wisteria Ribbon", "Wisteria", "Wisteria"],
+    ["Eight-Plank Bridge", "Iris Ribbon", "Iris", "Iris"],
+    ["Butterflies", "Peony Blue Ribbon", "Peony", "Peony"],
+    ["Boar", "Bush Clover Ribbon", "Bush Clover", "Bush Clover"],
+    ["Full Moon", "Geese", "Pampas Grass", "Pampas Grass"],
+    ["Sake Cup", "Chrysanthemum Blue Ribbon", "Chrysanthemum", "Chrysanthemum"],
+    ["Deer", "Maple Blue Ribbon", "Maple", "Maple"],
+    ["Rain Man", "Swallow", "Willow Ribbon", "Lightning"],
+    ["Phoenix", "Paulownia", "Paulownia", "Paulownia"],
+];
+
+const JAPANESE_NAMES: [[&str; 4]; 12] = [
+    ["松に鶴", "松に赤短", "松のカス", "松のカス"],
+    ["梅に鶯", "梅に赤短", "梅のカス", "梅のカス"],
+    ["桜に幕", "桜に赤短", "桜のカス", "桜のカス"],
+    ["藤に不如帰", "藤に短冊", "藤のカス", "藤のカス"],
+    ["菖蒲に八橋", "菖蒲に短冊", "菖蒲のカス", "菖蒲のカス"],
+    ["牡丹に蝶", "牡丹に青短", "牡丹のカス", "牡丹のカス"],
+    ["萩に猪", "萩に短冊", "萩のカス", "萩のカス"],
+    ["芒に月", "芒に雁", "芒のカス", "芒のカス"],
+    ["菊に盃", "菊に青短", "菊のカス", "菊のカス"],
+    ["紅葉に鹿", "紅葉に青短", "紅葉のカス", "紅葉のカス"],
+    ["柳に小野道風", "柳に燕", "柳に短冊", "柳のカス"],
+    ["桐に鳳凰", "桐のカス", "桐のカス", "桐のカス"],
+];
+
+impl Card {
+    /// The card of `rank` 1 to 4 in `month` 1 to 12, panics out of these ranges.
+    pub const fn new(month: u8, rank: u8) -> Self {
+        assert!(month >= 1 && month <= 12 && rank >= 1 && rank <= 4, "no such card");
+        Card((month - 1) * 4 + rank - 1)
+    }
+
+    /// The card at `index` 0 to 47 in the order of the cards, panics beyond.
+    pub fn from_index(index: usize) -> Self {
+        assert!(index < 48, "no card of index {index}");
+        Card(index as u8)
+    }
+
+    /// The 48 cards in order.
+    pub fn all() -> impl Iterator<Item = Card> {
+        (0..48).map(Card)
+    }
+
+    pub const fn month(self) -> u8 {
+        self.0 / 4 + 1
+    }
+
+    pub const fn rank(self) -> u8 {
+        self.0 % 4 + 1
+    }
+
+    /// Position of the card in the 48 cards.
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub const fn kind(self) -> CardKind {
+        KINDS[self.0 as usize / 4][self.0 as usize % 4]
+    }
+
+    /// Whether the card counts as a `kind` card in the yaku, the sake cup counting both
+    /// as an animal and as a chaff.
+    pub const fn counts_as(self, kind: CardKind) -> bool {
+        self.kind() as u8 == kind as u8 || (self.0 == SAKE_CUP.0 && matches!(kind, Chaff))
+    }
+
+    pub fn name(self) -> &'static str {
+        NAMES[self.0 as usize / 4][self.0 as usize % 4]
+    }
+
+    pub fn japanese_name(self) -> &'static str {
+        JAPANESE_NAMES[self.0 as usize / 4][self.0 as usize % 4]
+    }
+}
+
+impl fmt::Debug for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.month(), self.rank())
+    }
+}
+
+/// The English name of the card, or the Japanese one with `{:#}`.
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}", self.japanese_name())
+        } else {
+            write!(f, "{}", self.name())
+        }
+    }
+}
+
+impl From<Card> for (u8, u8) {
+    fn from(card: Card) -> Self {
+        (card.month(), card.rank())
+    }
+}
+
+impl TryFrom<(u8, u8)> for Card {
+    type Error = String;
+
+    fn try_from((month, rank): (u8, u8)) -> Result<Self, Self::Error> {
+        if (1..=12).contains(&month) && (1..=4).contains(&rank) {
+            Ok(Card::new(month, rank))
+        } else {
+            Err(format!("no card of month {month} and rank {rank}"))
+        }
+    }
+}
+
+/// A set of cards, bit `i` standing for the card of index `i`.
+/// Sets are serialized as lists of cards.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(into = "Vec<Card>", from = "Vec<Card>")]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub const EMPTY: CardSet = CardSet(0);
+    pub const ALL: CardSet = CardSet((1 << 48) - 1);
+
+    pub const fn from_bits(bits: u64) -> Self {
+        CardSet(bits & Self::ALL.0)
+    }
+
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub const fn of(cards: &[Card]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < cards.len() {
+            bits |= 1 << cards[i].0;
+            i += 1;
+        }
+        CardSet(bits)
+    }
+
+    /// The four cards of `month`, no card if `month` is not in `1..=12`.
+    pub const fn month(month: u8) -> Self {
+        if month < 1 || month > 12 {
+            return CardSet::EMPTY;
+        }
+        CardSet(0b1111 << ((month as u64 - 1) * 4))
+    }
+
+    /// The cards counting as `kind` cards, see [`Card::counts_as`].
+    pub const fn of_kind(kind: CardKind) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < 48 {
+            if Card(i).counts_as(kind) {
+                bits |= 1 << i;
+            }
+            i += 1;
+        }
+        CardSet(bits)
+    }
+
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, card: Card) -> bool {
+        self.0 & 1 << card.0 != 0
+    }
+
+    pub const fn is_subset(self, other: CardSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// Adds `card`, returns whether it was not in the set.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let absent = !self.contains(card);
+        self.0 |= 1 << card.0;
+        absent
+    }
+
+    /// Removes `card`, returns whether it was in the set.
+    pub fn remove(&mut self, card: Card) -> bool {
+        let present = self.contains(card);
+        self.0 &= !(1 << card.0);
+        present
+    }
+
+    /// The cards in order.
+    pub fn iter(self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+}
+
+/// Iterator over the cards of a [`CardSet`] in order.
+#[derive(Clone)]
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Some(Card(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for &CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> Self {
+        let mut set = CardSet::EMPTY;
+        set.extend(cards);
+        set
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        for card in cards {
+            self.insert(card);
+        }
+    }
+}
+
+impl From<CardSet> for Vec<Card> {
+    fn from(cards: CardSet) -> Self {
+        cards.iter().collect()
+    }
+}
+
+impl From<Vec<Card>> for CardSet {
+    fn from(cards: Vec<Card>) -> Self {
+        cards.into_iter().collect()
+    }
+}
+
+impl From<Card> for CardSet {
+    fn from(card: Card) -> Self {
+        CardSet(1 << card.0)
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+}
+
+impl Not for CardSet {
+    type Output = CardSet;
+
+    fn not(self) -> CardSet {
+        CardSet(!self.0 & Self::ALL.0)
+    }
+}
+
+impl BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, other: CardSet) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, other: CardSet) {
+        self.0 &= other.0;
+    }
+}
+
+impl SubAssign for CardSet {
+    fn sub_assign(&mut self, other: CardSet) {
+        self.0 &= !other.0;
+    }
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum State {
     Init,
+    /// A dealt hand wins the round at once, see [`RoundState::instant_win`].
+    InstantWin,
     Discard,
     DiscardPick,
     Draw,
@@ -14,64 +383,204 @@ pub enum State {
     RoundOver,
 }
 
-const DEFAULT_ROUND_TOTAL: u32 = 8;
-const DEFAULT_INIT_POINT: u32 = 30;
+/// A move of the turn player, see [`RoundState::legal_actions`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Action {
+    /// Discards a card from the hand.
+    Discard(Card),
+    /// Picks which of two pairing field cards to collect.
+    Pick(Card),
+    /// Draws the top card of the stock.
+    Draw,
+    /// Calls koi-koi (`true`) or stops the round (`false`) after a new yaku.
+    KoiKoi(bool),
+    /// Goes on when the state leaves no choice.
+    Continue,
+}
+
+/// One of the two players, `First` being the one of index 0 in the arrays of both players.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(into = "usize", try_from = "usize")]
+pub enum Player {
+    First,
+    Second,
+}
 
-const CRANE: [Card; 1] = [(1,1)];
-const CURTAIN: [Card; 1] = [(3,1)];
-const MOON: [Card; 1] = [(8,1)];
-const RAIN_MAN:  [Card; 1] = [(11,1)];
-const PHOENIX: [Card; 1] = [(12,1)];
-const SAKE: [Card; 1] = [(9,1)];
+impl Player {
+    pub const BOTH: [Player; 2] = [Player::First, Player::Second];
 
-const LIGHT: [Card; 5] = [(1,1),(3,1),(8,1),(11,1),(12,1)];
-const SEED:  [Card; 9] = [(2,1),(4,1),(5,1),(6,1),(7,1),(8,2),(9,1),(10,1),(11,2)];
-const RIBBON: [Card; 10] = [(1,2),(2,2),(3,2),(4,2),(5,2),(6,2),(7,2),(9,2),(10,2),(11,3)];
-const DROSS: [Card; 25] = [(1,3),(1,4),(2,3),(2,4),(3,3),(3,4),(4,3),(4,4),(5,3),(5,4),(6,3),(6,4),(7,3),
-          (7,4),(8,3),(8,4),(9,3),(9,4),(10,3),(10,4),(11,4),(12,2),(12,3),(12,4),(9,1)];
-        
-const BOAR_DEER_BUTTERFLY: [Card; 3] = [(6,1),(7,1),(10,1)];
-const FLOWER_SAKE: [Card; 2] = [(3,1),(9,1)];
-const MOON_SAKE: [Card; 2] = [(8,1),(9,1)];
-const RED_RIBBON: [Card; 3] = [(1,2),(2,2),(3,2)];
-const BLUE_RIBBON: [Card; 3] = [(6,2),(9,2),(10,2)];
-const RED_BLUE_RIBBON: [Card; 6] = [(1,2),(2,2),(3,2),(6,2),(9,2),(10,2)];
-
-lazy_static! {
-    pub static ref CARD_LIST: Vec<Vec<Card>> = vec![
-        CRANE.to_vec(),
-        CURTAIN.to_vec(),
-        RAIN_MAN.to_vec(),
-        PHOENIX.to_vec(),
-        SAKE.to_vec(),
-        BOAR_DEER_BUTTERFLY.to_vec(),
-        SEED.to_vec(),
-        RED_RIBBON.to_vec(),
-        BLUE_RIBBON.to_vec(),
-        RED_BLUE_RIBBON.to_vec(),
-        RIBBON.to_vec(),
-        DROSS.to_vec(),
-    ];
-}
-
-
-type CardLog = [[[f32; 48]; 8]; 16];
+    pub fn opponent(self) -> Self {
+        match self {
+            Player::First => Player::Second,
+            Player::Second => Player::First,
+        }
+    }
 
+    /// Index of the player in the arrays of both players.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Player::First => write!(f, "first player"),
+            Player::Second => write!(f, "second player"),
+        }
+    }
+}
+
+impl From<Player> for usize {
+    fn from(player: Player) -> Self {
+        player.index()
+    }
+}
+
+impl TryFrom<usize> for Player {
+    type Error = String;
+
+    fn try_from(index: usize) -> Result<Self, Self::Error> {
+        match index {
+            0 => Ok(Player::First),
+            1 => Ok(Player::Second),
+            _ => Err(format!("no player of index {index}")),
+        }
+    }
+}
+
+impl<T> Index<Player> for [T; 2] {
+    type Output = T;
+
+    fn index(&self, player: Player) -> &T {
+        &self[player.index()]
+    }
+}
+
+impl<T> IndexMut<Player> for [T; 2] {
+    fn index_mut(&mut self, player: Player) -> &mut T {
+        &mut self[player.index()]
+    }
+}
+
+/// Why an action was rejected, the state is left untouched.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameError {
+    /// The action does not belong to the current state.
+    WrongPhase(State),
+    /// The discarded card is not in the turn player's hand.
+    CardNotInHand(Card),
+    /// The picked card does not pair with the shown card.
+    CardNotPairable(Card),
+    /// The state needs a pick or a koi-koi decision, not `Continue`.
+    ChoiceRequired,
+    /// A pick or a koi-koi decision was given where there is no choice.
+    UnexpectedChoice,
+    /// There is no card left to draw.
+    EmptyStock,
+    /// The round is over and takes no more actions.
+    RoundOver,
+    /// The game is over and takes no more actions.
+    GameOver,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::WrongPhase(state) => write!(f, "action not allowed in state {state:?}"),
+            GameError::CardNotInHand(card) => write!(f, "card {card:?} is not in hand"),
+            GameError::CardNotPairable(card) => write!(f, "card {card:?} does not pair with the shown card"),
+            GameError::ChoiceRequired => write!(f, "a choice is required"),
+            GameError::UnexpectedChoice => write!(f, "there is no choice to make"),
+            GameError::EmptyStock => write!(f, "the stock is empty"),
+            GameError::RoundOver => write!(f, "the round is over"),
+            GameError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+const DEFAULT_INIT_POINT: i32 = 30;
+
+const CRANE: Card = Card::new(1,1);
+const CURTAIN: Card = Card::new(3,1);
+const MOON: Card = Card::new(8,1);
+const RAIN_MAN: Card = Card::new(11,1);
+const PHOENIX: Card = Card::new(12,1);
+const SAKE_CUP: Card = Card::new(9,1);
+
+const LIGHT: CardSet = CardSet::of_kind(Bright);
+const SEED: CardSet = CardSet::of_kind(Animal);
+const RIBBON: CardSet = CardSet::of_kind(Ribbon);
+const DROSS: CardSet = CardSet::of_kind(Chaff);
+
+const BOAR_DEER_BUTTERFLY: CardSet = CardSet::of(&[Card::new(6,1),Card::new(7,1),Card::new(10,1)]);
+const FLOWER_SAKE: CardSet = CardSet::of(&[CURTAIN,SAKE_CUP]);
+const MOON_SAKE: CardSet = CardSet::of(&[MOON,SAKE_CUP]);
+const RED_RIBBON: CardSet = CardSet::of(&[Card::new(1,2),Card::new(2,2),Card::new(3,2)]);
+const BLUE_RIBBON: CardSet = CardSet::of(&[Card::new(6,2),Card::new(9,2),Card::new(10,2)]);
+const RED_BLUE_RIBBON: CardSet = CardSet::from_bits(RED_RIBBON.bits() | BLUE_RIBBON.bits());
+
+fn has_four_of_month(cards: CardSet) -> bool {
+    (1..13).any(|month| CardSet::month(month).is_subset(cards))
+}
+
+/// The groups of cards of the yaku.
+pub const CARD_LIST: [CardSet; 13] = [
+    CardSet::of(&[CRANE]),
+    CardSet::of(&[CURTAIN]),
+    CardSet::of(&[MOON]),
+    CardSet::of(&[RAIN_MAN]),
+    CardSet::of(&[PHOENIX]),
+    CardSet::of(&[SAKE_CUP]),
+    BOAR_DEER_BUTTERFLY,
+    SEED,
+    RED_RIBBON,
+    BLUE_RIBBON,
+    RED_BLUE_RIBBON,
+    RIBBON,
+    DROSS,
+];
+
+/// Multi-hot rows of the cards played at each of the 16 turns. The 8 rows of a turn are
+/// the discarded card, the field cards pairing with it, the picked one when there was a
+/// choice and the field cards collected, then the same four rows for the drawn card.
+pub type CardLog = [[[f32; 48]; 8]; 16];
+
+pub const LOG_DISCARD: usize = 0;
+pub const LOG_DRAW: usize = 4;
+pub const LOG_PAIRING: usize = 1;
+pub const LOG_PICK: usize = 2;
+pub const LOG_COLLECT: usize = 3;
+
+
+/// The cards of a round before the first discard.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Deal {
+    pub hands: [Vec<Card>; 2],
+    pub field: Vec<Card>,
+    /// Cards left to draw, the last one first.
+    pub stock: Vec<Card>,
+}
+
+#[derive(Clone)]
 pub struct RoundState {
-    pub hand: [Vec<Card>; 2],
-    pub pile: [Vec<Card>; 2],
-    pub field_slot: Vec<Card>,
+    pub hand: [CardSet; 2],
+    pub pile: [CardSet; 2],
+    pub field: CardSet,
+    /// Cards left to draw, the last one first.
     pub stock: Vec<Card>,
     
-    pub init_board: Vec<Card>,
+    pub init_board: CardSet,
 
     pub show: Vec<Card>,
-    pub collect: Vec<Card>,
+    pub collect: CardSet,
     
     pub turn_16: usize,
-    pub dealer: usize,
+    pub dealer: Player,
     pub koikoi: [[i32; 8]; 2],
-    winner: Option<usize>,
+    winner: Option<Player>,
     exhausted: bool,
     turn_point: i32,
     
@@ -79,274 +588,335 @@ pub struct RoundState {
     wait_action: bool,
 
     pub card_log: CardLog,
+    pub rules: RuleSet,
 }
 
 impl RoundState {
-    /*
-    fn new(dealer: Option<usize>) -> Self {
-        let hand = [vec!(), vec!()];
-        let pile = [vec!(), vec!()];
-        let field_slot = vec!();
-        let stock = vec!();
-        
-        let show = vec!();
-        let collect = vec!();
-        
-        let turn_16 = 1;
-        let dealer = match dealer {
-            Some(d) => d,
-            None => 1, // todo
-        };
-        
-        let koikoi = [[0,0,0,0,0,0,0,0], [0,0,0,0,0,0,0,0]];
-        let winner = None;
-        let exhausted = false;
-        
-        let turn_point = 0;
-        
-        let state = State::Init;
-        let wait_action = false;
+    /// Starts a new round dealt by `dealer`, shuffling the cards with `rng`.
+    pub fn new<R: Rng + ?Sized>(dealer: Player, rules: RuleSet, rng: &mut R) -> Self {
+        let mut round = Self::empty(dealer, rules);
+        round.deal_card(rng);
+        round
+    }
+
+    /// Starts a new round dealt by `dealer` with the given cards.
+    pub fn from_deal(dealer: Player, rules: RuleSet, deal: &Deal) -> Self {
+        let mut round = Self::empty(dealer, rules);
+        round.hand = deal.hands.each_ref().map(|hand| hand.iter().copied().collect());
+        round.init_board = deal.field.iter().copied().collect();
+        round.field = round.init_board;
+        round.stock = deal.stock.clone();
+        round.start();
+        round
+    }
 
+    fn empty(dealer: Player, rules: RuleSet) -> Self {
         Self {
-            hand,
-            pile,
-            field_slot,
-            stock,
-            show,
-            collect,
-            turn_16,
+            hand: [CardSet::EMPTY; 2],
+            pile: [CardSet::EMPTY; 2],
+            field: CardSet::EMPTY,
+            stock: vec!(),
+            init_board: CardSet::EMPTY,
+            show: vec!(),
+            collect: CardSet::EMPTY,
+            turn_16: 1,
             dealer,
-            koikoi,
-            winner,
-            exhausted,
-            turn_point,
-            state,
-            wait_action,
-        } 
+            koikoi: [[0; 8]; 2],
+            winner: None,
+            exhausted: false,
+            turn_point: 0,
+            state: State::Init,
+            wait_action: false,
+            card_log: [[[0.; 48]; 8]; 16],
+            rules,
+        }
     }
-    */
-
-    fn new_round(&mut self) {
 
+    /// The dealer plays the odd turns.
+    pub fn turn_player(&self) -> Player {
+        if self.turn_16 % 2 == 1 { self.dealer } else { self.dealer.opponent() }
     }
 
-    pub fn turn_player(&self) -> usize {
-        if (self.turn_16+self.dealer)%2==0 {1} else {2}
+    fn turn_8(&self) -> usize {
+        self.turn_16.div_ceil(2)
     }
 
-    fn turn_8(&self) -> usize {
-        (self.turn_16+1)/2
+    pub fn unseen_cards(&self, player: Player) -> CardSet {
+        self.stock.iter().copied().collect::<CardSet>() | self.hand[player.opponent()]
     }
 
-    pub fn field(&self) -> Vec<Card> {
-        let mut res: Vec<_> = self.field_slot
-            .iter()
-            .filter(|(x, y)| *x != 0 || *y != 0)
-            .copied()
-            .collect();
-        res.sort_unstable();
-        res
+    /// What `player` knows of the round, see [`Observation`].
+    pub fn observation(&self, player: Player) -> Observation {
+        let unseen = self.unseen_cards(player);
+        let mut round = self.clone();
+        let opponent_hand_len = round.hand[player.opponent()].len();
+        round.hand[player.opponent()] = CardSet::EMPTY;
+        round.stock.clear();
+        Observation { player, round, unseen, opponent_hand_len }
     }
 
-    pub fn unseen_cards(&self, player: usize) -> Vec<Card> {
-        let mut unseen = self.stock.clone();
-        if player == 0 {
-            unseen.extend(&self.hand[1]);
-        } else {
-            unseen.extend(&self.hand[0]);
+    /// The field cards of the month of the shown card.
+    pub fn pairing_cards(&self) -> CardSet {
+        match self.show.first() {
+            Some(show) => self.field & CardSet::month(show.month()),
+            None => CardSet::EMPTY,
         }
-        unseen
     }
 
-    pub fn pairing_cards(&self) -> Vec<Card> {
-        self.field().iter().filter(|&&(c, _)| c == self.show[0].0).copied().collect()
+    fn field_collect(&self) -> CardSet {
+        self.collect - self.show.iter().copied().collect()
     }
 
-    fn field_collect(&self) -> Vec<Card> {
-        self.collect
-            .iter()
-            .filter(|&&c| c != self.show[0])
-            .copied()
-            .collect()
+    fn log_cards(&mut self, row: usize, cards: impl IntoIterator<Item = Card>) {
+        for card in cards {
+            self.card_log[self.turn_16 - 1][row][card.index()] = 1.;
+        }
     }
 
-    fn round_points(&self, player: usize) -> Option<i32> {
-        if self.winner.is_none() {
-            None
-        } else if self.exhausted {
-            if (self.dealer==0) == (player == 0) { Some(1) } else { Some(-1) }
-        } else if self.winner == Some(0) {
-            if player == 0 {Some(self.yaku_points(0))} else {Some(-self.yaku_points(0))}
+    /// Points won (or lost, if negative) by `player` once the round is over.
+    pub fn round_points(&self, player: Player) -> Option<i32> {
+        let winner = self.winner?;
+        let points = if self.exhausted {
+            1
+        } else if self.instant_win(winner).is_some() {
+            self.rules.instant_win_points
         } else {
-            if player == 1 {Some(self.yaku_points(1))} else {Some(-self.yaku_points(1))}    
-        }
+            self.yaku_points(winner)
+        };
+        if player == winner { Some(points) } else { Some(-points) }
     }
 
-    pub fn koikoi_num(&self, player: usize) -> i32 {
+    pub fn koikoi_num(&self, player: Player) -> i32 {
         self.koikoi[player].iter().sum()
     }
 
 
-    pub fn yaku_points(&self, player: usize) -> i32 {
-        let mut point = 0; // todo
-        /* 
-             self.yaku(player)
-            .filter()
-            .map
-
-        sum([yaku[2] for yaku in self.yaku(player) if yaku[1]!='Koi-Koi'])
-        */
-        let koikoi_num = self.koikoi_num(player);
-        if koikoi_num <= 3 {
-            point += koikoi_num
+    /// Points of the yaku made by `player`, including the koi-koi bonus of the rules,
+    /// see [`KoiKoiBonus`].
+    pub fn yaku_points(&self, player: Player) -> i32 {
+        let mut point: i32 = self.yaku(player)
+            .iter()
+            .filter(|(_, name, _)| *name != "Koi-Koi")
+            .map(|(_, _, point)| point)
+            .sum();
+        if self.rules.double_at_seven && point >= 7 {
+            point *= 2;
         }
-        else {
-            point *= koikoi_num - 2
-    
+        let koikoi_num = self.koikoi_num(player);
+        match self.rules.koikoi_bonus {
+            KoiKoiBonus::Additive if koikoi_num <= 3 => point += koikoi_num,
+            KoiKoiBonus::Additive => point *= koikoi_num - 2,
+            KoiKoiBonus::Doubling if self.koikoi_num(player.opponent()) > 0 => point *= 2,
+            KoiKoiBonus::Doubling | KoiKoiBonus::None => {}
         }
         point
     }
 
-    fn _deal_card(&mut self) {
+    /// The cards counting as chaff under the rules.
+    fn dross(&self) -> CardSet {
+        if self.rules.sake_cup_chaff { DROSS } else { DROSS - CardSet::from(SAKE_CUP) }
+    }
+
+    fn deal_card<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut cards: Vec<Card> = Card::all().collect();
         loop {
-            let mut cards: Vec<Card> = vec!();
-            for i in 1..13 {
-                for j in 1..5 {
-                    cards.push((i, j));
-                }
-            }
-            //random.shuffle(card);   todo
-            let mut hand1 = cards[0..8].to_vec();
-            hand1.sort_unstable();
-            let mut hand2 = cards[8..16].to_vec();
-            hand2.sort_unstable();
-            self.hand = [hand1, hand2];
-            let mut field_slot = cards[16..24].to_vec();
-            field_slot.sort_unstable();
-            for i in 0..10 {
-                field_slot.push((0, 0));
+            cards.shuffle(rng);
+            let hand1: CardSet = cards[0..8].iter().copied().collect();
+            let hand2: CardSet = cards[8..16].iter().copied().collect();
+            let field: CardSet = cards[16..24].iter().copied().collect();
+            // the field holding the four cards of a month is redealt, and so are such hands
+            // when they do not win at once
+            let redeal = has_four_of_month(field)
+                || !self.rules.instant_wins && (has_four_of_month(hand1) || has_four_of_month(hand2));
+            if !redeal {
+                self.hand = [hand1, hand2];
+                self.init_board = field;
+                self.field = field;
+                self.stock = cards[24..].to_vec();
+                break
             }
-            self.field_slot = field_slot;
-            self.stock = cards[24..].to_vec();     
-            let mut flag = true;
-            for suit in 1..13 {
-                /* 
-                if 4 in [[card[0] for card in self.hand[1]].count(suit),
-                         [card[0] for card in self.hand[2]].count(suit),
-                         [card[0] for card in self.field].count(suit)]:
-                    flag = false; 
-                */
-                flag = false;
+        }
+        self.start();
+    }
+
+    fn start(&mut self) {
+        if Player::BOTH.iter().any(|&player| self.instant_win(player).is_some()) {
+            self.state = State::InstantWin;
+            self.wait_action = false;
+        } else {
+            self.state = State::Discard;
+            self.wait_action = true;
+        }
+    }
+
+    /// The hand winning at once dealt to `player`, if the rules allow it: four cards of a month
+    /// (teshi) or four pairs of a month (kuttsuki).
+    pub fn instant_win(&self, player: Player) -> Option<&'static str> {
+        let hand = self.hand[player];
+        if !self.rules.instant_wins || self.turn_16 > 1 || hand.len() != 8 {
+            None
+        } else if has_four_of_month(hand) {
+            Some("Teshi")
+        } else if (1..13).all(|month| (hand & CardSet::month(month)).len().is_multiple_of(2)) {
+            Some("Kuttsuki")
+        } else {
+            None
+        }
+    }
+
+    /// Lists the actions accepted by [`RoundState::apply`] in the current state.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        match self.state {
+            State::Discard => self.hand[self.turn_player()].iter().map(Action::Discard).collect(),
+            State::DiscardPick | State::DrawPick => {
+                if self.wait_action {
+                    self.pairing_cards().into_iter().map(Action::Pick).collect()
+                } else {
+                    vec![Action::Continue]
+                }
             }
-            if flag {
-                break
+            State::Draw => vec![Action::Draw],
+            State::KoiKoi => {
+                if self.wait_action {
+                    vec![Action::KoiKoi(true), Action::KoiKoi(false)]
+                } else {
+                    vec![Action::Continue]
+                }
             }
-        }   
-        self.state = State::Discard;
-        self.wait_action = true;
-        return
+            State::InstantWin => vec![Action::Continue],
+            State::Init | State::RoundOver => vec![],
+        }
+    }
+
+    /// Plays `action` for the turn player, the state is unchanged if it is rejected.
+    pub fn apply(&mut self, action: Action) -> Result<(), GameError> {
+        match (action, self.state) {
+            (Action::Discard(card), _) => self.discard(card),
+            (Action::Pick(card), State::DrawPick) => self.draw_pick(Some(card)),
+            (Action::Pick(card), _) => self.discard_pick(Some(card)),
+            (Action::Draw, _) => self.draw(),
+            (Action::KoiKoi(is_koikoi), _) => self.claim_koikoi(Some(is_koikoi)),
+            (Action::Continue, State::DiscardPick) => self.discard_pick(None),
+            (Action::Continue, State::DrawPick) => self.draw_pick(None),
+            (Action::Continue, State::InstantWin) => self.claim_instant_win(),
+            (Action::Continue, _) => self.claim_koikoi(None),
+        }
+    }
+
+    fn expect_state(&self, state: State) -> Result<(), GameError> {
+        if self.state == state {
+            Ok(())
+        } else if self.state == State::RoundOver {
+            Err(GameError::RoundOver)
+        } else {
+            Err(GameError::WrongPhase(self.state))
+        }
+    }
+
+    fn expect_pick(&self, card: Option<Card>) -> Result<(), GameError> {
+        match (self.wait_action, card) {
+            (true, Some(card)) if !self.pairing_cards().contains(card) => Err(GameError::CardNotPairable(card)),
+            (true, Some(_)) => Ok(()),
+            (true, None) => Err(GameError::ChoiceRequired),
+            (false, Some(_)) => Err(GameError::UnexpectedChoice),
+            (false, None) => Ok(()),
+        }
     }
 
-    fn _collect_card(&mut self, card: Card) {
+    fn collect_card(&mut self, card: Option<Card>) {
+        let log_offset = if self.state == State::DrawPick { LOG_DRAW } else { LOG_DISCARD };
+        if let Some(card) = card {
+            self.log_cards(log_offset + LOG_PICK, [card]);
+        }
         let pairing_card = self.pairing_cards();
         let n = pairing_card.len();
+        let show = CardSet::from(self.show[0]);
         if pairing_card.is_empty() {
-            self.collect = Vec::new();
-            if let Some(index) = self.field_slot.iter().position(|&slot| slot == (0, 0)) {
-                self.field_slot[index] = self.show[0];
-            }
+            self.collect = CardSet::EMPTY;
+            self.field |= show;
         }
         else if n == 1 || n == 3 {
-            self.collect = self.show.clone();
-            self.collect.extend(&pairing_card);
-            for paired_card in &pairing_card {
-                if let Some(index) = self.field_slot.iter().position(|&slot| slot == *paired_card) {
-                    self.field_slot[index] = (0, 0);
-                }
-            };
-            self.pile[self.turn_player()].extend(&self.collect);
+            self.collect = show | pairing_card;
+            self.field -= pairing_card;
+            let turn_player = self.turn_player();
+            self.pile[turn_player] |= self.collect;
         }
-        else {
-            self.collect = self.show.clone();
-            self.collect.push(card);
-            if let Some(index) = self.field_slot.iter().position(|&slot| slot == card) {
-                self.field_slot[index] = (0, 0);
-            }
-            self.pile[self.turn_player()].extend(&self.collect);
+        else if let Some(card) = card {
+            self.collect = show | CardSet::from(card);
+            self.field.remove(card);
+            let turn_player = self.turn_player();
+            self.pile[turn_player] |= self.collect;
         }
+        let field_collect = self.field_collect();
+        self.log_cards(log_offset + LOG_COLLECT, field_collect);
     }
 
-    fn discard(&mut self, card: Card) {
+    fn discard(&mut self, card: Card) -> Result<(), GameError> {
+        self.expect_state(State::Discard)?;
         let turn_player = self.turn_player();
-        // Vérification des préconditions
-        // assert_eq!(self.state, State::Discard);
-        assert!(self.hand[turn_player].contains(&card));
+        if !self.hand[turn_player].remove(card) {
+            return Err(GameError::CardNotInHand(card));
+        }
 
         self.turn_point = self.yaku_points(turn_player);
-        if let Some(ind) = self.hand[turn_player].iter().position(|&c| c == card) {
-            self.show = vec![self.hand[turn_player].remove(ind)];
-        }
+        self.show = vec![card];
+        self.log_cards(LOG_DISCARD, [card]);
+        self.log_cards(LOG_DISCARD + LOG_PAIRING, self.pairing_cards());
 
         self.state = State::DiscardPick;
         self.wait_action = self.pairing_cards().len() == 2;
-
-        // Retourner l'état ou appeler __call__
-        /*
-        if self.silence {
-            self.state.clone()
-        } else {
-            self.call()
-        }
-        */
+        Ok(())
     }
 
-    fn discard_pick(&mut self, card: Option<Card>) {
-        /*assert_eq!(self.state, State.DiscardPick);
-        
-        if self.wait_action {
-            assert!(card.is_some() && self.pairing_card.contains(&card.unwrap()));
-        } else {
-            assert!(card.is_none());
-        }
-        */
+    fn discard_pick(&mut self, card: Option<Card>) -> Result<(), GameError> {
+        self.expect_state(State::DiscardPick)?;
+        self.expect_pick(card)?;
 
-        if let Some(c) = card {
-            self._collect_card(c);
-        }
+        self.collect_card(card);
 
         self.state = State::Draw;
         self.wait_action = false;
+        Ok(())
     }    
 
-    fn draw(&mut self) {
-        //assert_eq!(self.state, "draw");
+    fn draw(&mut self) -> Result<(), GameError> {
+        self.expect_state(State::Draw)?;
 
-        if let Some(c) = self.stock.pop() {
-            self.show = vec![c];
-        }
+        let card = self.stock.pop().ok_or(GameError::EmptyStock)?;
+        self.show = vec![card];
+        self.log_cards(LOG_DRAW, [card]);
+        self.log_cards(LOG_DRAW + LOG_PAIRING, self.pairing_cards());
 
         self.state = State::DrawPick;
         self.wait_action = self.pairing_cards().len() == 2;
+        Ok(())
     }
 
-    fn draw_pick(&mut self, card: Card) {
-        //assert self.state == 'draw-pick'
-        //assert (card in self.pairing_card) if self.wait_action else (card == None)
-        
-        self._collect_card(card);
+    fn draw_pick(&mut self, card: Option<Card>) -> Result<(), GameError> {
+        self.expect_state(State::DrawPick)?;
+        self.expect_pick(card)?;
+
+        self.collect_card(card);
 
         self.state = State::KoiKoi;
         self.wait_action = (self.yaku_points(self.turn_player()) > self.turn_point) && (self.turn_8() < 8);   
+        Ok(())
     }
 
-    fn claim_koikoi(&mut self, mut is_koikoi: Option<bool>) {
+    fn claim_koikoi(&mut self, mut is_koikoi: Option<bool>) -> Result<(), GameError> {
+        self.expect_state(State::KoiKoi)?;
+        match (self.wait_action, is_koikoi) {
+            (true, None) => return Err(GameError::ChoiceRequired),
+            (false, Some(_)) => return Err(GameError::UnexpectedChoice),
+            _ => {}
+        }
         let turn_player = self.turn_player();
         let turn_8 = self.turn_8();
         // Action
         if self.yaku_points(turn_player) > self.turn_point && turn_8 == 8 {
             is_koikoi = Some(false);
         }
-        self.koikoi[turn_player][turn_8 as usize - 1] = if is_koikoi.unwrap_or(false) { 1 } else { 0 };
+        self.koikoi[turn_player][turn_8 - 1] = if is_koikoi.unwrap_or(false) { 1 } else { 0 };
 
         if is_koikoi == Some(false) {
             self.state = State::RoundOver;
@@ -362,80 +932,93 @@ impl RoundState {
             self.state = State::Discard;
             self.wait_action = true;
         }
+        Ok(())
+    }
+
+    fn claim_instant_win(&mut self) -> Result<(), GameError> {
+        self.expect_state(State::InstantWin)?;
+        // the dealer wins when both hands do
+        self.winner = [self.dealer, self.dealer.opponent()].into_iter().find(|&player| self.instant_win(player).is_some());
+        self.state = State::RoundOver;
+        Ok(())
     }
 
-    fn yaku(&self, player: usize) -> Vec<(i32, &'static str, i32)> {
+    /// Yaku made by `player` as `(id, name, points)`.
+    pub fn yaku(&self, player: Player) -> Vec<(i32, &'static str, i32)> {
         let mut yaku = Vec::new();
-        let pile: HashSet<Card> = self.pile[player].iter().cloned().collect();
+        let pile = self.pile[player];
         let koikoi_num = self.koikoi_num(player);
 
-        let num_light = LIGHT.iter().filter(|c| pile.contains(c)).count();
+        let num_light = (pile & LIGHT).len();
         
         if num_light == 5 {
             yaku.push((1, "Five Lights", 10));
-        } else if num_light == 4 && !pile.contains(&(11, 1)) {
+        } else if num_light == 4 && !pile.contains(RAIN_MAN) {
             yaku.push((2, "Four Lights", 8));
         } else if num_light == 4 {
             yaku.push((3, "Rainy Four Lights", 7));
-        } else if num_light == 3 && !pile.contains(&(11, 1)) {
+        } else if num_light == 3 && !pile.contains(RAIN_MAN) {
             yaku.push((4, "Three Lights", 5));
         }
 
-        let num_seed = SEED.iter().filter(|c| pile.contains(c)).count();
-        if BOAR_DEER_BUTTERFLY.iter().all(|c| pile.contains(c)) {
+        let num_seed = (pile & SEED).len();
+        if BOAR_DEER_BUTTERFLY.is_subset(pile) {
             yaku.push((5, "Boar-Deer-Butterfly", 5));
         }
-        if FLOWER_SAKE.iter().all(|c| pile.contains(c)) {
-            if koikoi_num == 0 {
-                yaku.push((6, "Flower Viewing Sake", 1));
-            } else {
-                yaku.push((7, "Flower Viewing Sake", 3));
+        if self.rules.viewing_yaku {
+            let [before, after] = self.rules.viewing_points;
+            if FLOWER_SAKE.is_subset(pile) {
+                if koikoi_num == 0 {
+                    yaku.push((6, "Flower Viewing Sake", before));
+                } else {
+                    yaku.push((7, "Flower Viewing Sake", after));
+                }
             }
-        }
-        if MOON_SAKE.iter().all(|c| pile.contains(c)) {
-            if koikoi_num == 0 {
-                yaku.push((8, "Moon Viewing Sake", 1));
-            } else {
-                yaku.push((9, "Moon Viewing Sake", 3));
+            if MOON_SAKE.is_subset(pile) {
+                if koikoi_num == 0 {
+                    yaku.push((8, "Moon Viewing Sake", before));
+                } else {
+                    yaku.push((9, "Moon Viewing Sake", after));
+                }
             }
         }
         if num_seed >= 5 {
             yaku.push((10, "Tane", (num_seed - 4) as i32));
         }
 
-        let num_ribbon = RIBBON.iter().filter(|c| pile.contains(c)).count();
+        let num_ribbon = (pile & RIBBON).len();
         
         // TODO a verifier les if / else
-        if RED_RIBBON.iter().all(|c| pile.contains(c)) {
-            if BLUE_RIBBON.iter().all(|c| pile.contains(c)) {
+        if RED_RIBBON.is_subset(pile) {
+            if BLUE_RIBBON.is_subset(pile) {
                 yaku.push((11, "Red & Blue Ribbons", 10));
             } else  {
                 yaku.push((12, "Red Ribbons", 5));
             }
-        } else if BLUE_RIBBON.iter().all(|c| pile.contains(c)) {
+        } else if BLUE_RIBBON.is_subset(pile) {
             yaku.push((13, "Blue Ribbons", 5));
         }
         if num_ribbon >= 5 {
             yaku.push((14, "Tan", (num_ribbon - 4) as i32));
         }
 
-        let num_dross = DROSS.iter().filter(|c| pile.contains(c)).count();
+        let num_dross = (pile & self.dross()).len();
         if num_dross >= 10 {
             yaku.push((15, "Kasu", (num_dross - 9) as i32));
         }
 
         if koikoi_num > 0 {
-            yaku.push((16, "Koi-Koi", koikoi_num as i32));
+            yaku.push((16, "Koi-Koi", koikoi_num));
         }
 
         yaku
     }
 
-    fn call(&self, view: Option<usize>) {
+    pub fn call(&self, view: Option<Player>) {
         let view = view.unwrap_or(self.turn_player());
-        let op_view = 1 - view;
-        let pile: HashSet<Card> = self.pile[view].iter().cloned().collect();
-        let op_pile: HashSet<Card> = self.pile[op_view].iter().cloned().collect();
+        let op_view = view.opponent();
+        let pile = self.pile[view];
+        let op_pile = self.pile[op_view];
 
         println!("Turn: {},  State: {:?}", self.turn_8(), self.state);
         println!("-----------------------------------------------");
@@ -447,10 +1030,10 @@ impl RoundState {
         println!("Total Point: {}", self.yaku_points(op_view));
         println!("-----------------------------------------------");
         println!("Opponent's Pile:");
-        println!("Light: {:?}", LIGHT.iter().filter(|c| op_pile.contains(c)).count());
-        println!("Seed: {:?}", SEED.iter().filter(|c| op_pile.contains(c)).count());
-        println!("Ribbon: {:?}", RIBBON.iter().filter(|c| op_pile.contains(c)).count());
-        println!("Dross: {:?}", DROSS.iter().filter(|c| op_pile.contains(c)).count());
+        println!("Light: {:?}", (op_pile & LIGHT).len());
+        println!("Seed: {:?}", (op_pile & SEED).len());
+        println!("Ribbon: {:?}", (op_pile & RIBBON).len());
+        println!("Dross: {:?}", (op_pile & self.dross()).len());
         println!("-----------------------------------------------");
         println!("Opponent's Hand:");
         for _card in &self.hand[op_view] {
@@ -458,16 +1041,16 @@ impl RoundState {
         }
         println!("-----------------------------------------------");
         println!("Field:");
-        println!("{:?}", self.field());
+        println!("{:?}", self.field);
         println!("-----------------------------------------------");
         println!("Your Hand:");
         println!("{:?}", self.hand[view]);
         println!("-----------------------------------------------");
         println!("Your Pile:");
-        println!("Light: {:?}", LIGHT.iter().filter(|c| pile.contains(c)).count());
-        println!("Seed: {:?}", SEED.iter().filter(|c| pile.contains(c)).count());
-        println!("Ribbon: {:?}", RIBBON.iter().filter(|c| pile.contains(c)).count());
-        println!("Dross: {:?}", DROSS.iter().filter(|c| pile.contains(c)).count());
+        println!("Light: {:?}", (pile & LIGHT).len());
+        println!("Seed: {:?}", (pile & SEED).len());
+        println!("Ribbon: {:?}", (pile & RIBBON).len());
+        println!("Dross: {:?}", (pile & self.dross()).len());
         println!("-----------------------------------------------");
         println!("Your Yaku:");
         let your_yaku: Vec<(i32, &str, i32)> = self.yaku(view);
@@ -484,36 +1067,40 @@ impl RoundState {
 
         match self.state {
             State::Discard => {
-                println!("Use discard(card) to discard from hand.");
+                println!("Use Action::Discard(card) to discard from hand.");
             }
             State::DiscardPick => {
                 println!("Discard: {:?}", self.show[0]);
                 println!("Pairing: {:?}", self.pairing_cards());
                 if self.wait_action {
-                    println!("Use discard_pick(card) to pick a pairing field card.");
+                    println!("Use Action::Pick(card) to pick a pairing field card.");
                 } else {
-                    println!("Use discard_pick() to continue.");
+                    println!("Use Action::Continue to continue.");
                 }
             }
             State::Draw => {
-                println!("Use draw() to draw from stock.");
+                println!("Use Action::Draw to draw from stock.");
             }
             State::DrawPick => {
                 println!("Draw: {:?}", self.show[0]);
                 println!("Pairing: {:?}", self.pairing_cards());
                 if self.wait_action {
-                    println!("Use draw_pick(card) to pick a pairing field card.");
+                    println!("Use Action::Pick(card) to pick a pairing field card.");
                 } else {
-                    println!("Use draw_pick() to continue.");
+                    println!("Use Action::Continue to continue.");
                 }
             }
             State::KoiKoi => {
                 if self.wait_action {
-                    println!("Use claim_koikoi(bool) to koikoi or stop.");
+                    println!("Use Action::KoiKoi(bool) to koikoi or stop.");
                 } else {
-                    println!("Use claim_koikoi() to continue.");
+                    println!("Use Action::Continue to continue.");
                 }
             }
+            State::InstantWin => {
+                println!("Instant Win: You {:?}, Opponent {:?}", self.instant_win(view), self.instant_win(op_view));
+                println!("Use Action::Continue to continue.");
+            }
             State::RoundOver => {
                 println!("Round Over");
                 println!("Round Point: You {:?}, Opponent {:?}", self.round_points(view), self.round_points(op_view));
@@ -523,15 +1110,188 @@ impl RoundState {
     }
 }
 
+/// What a player knows of a round. The hand of the opponent and the stock are hidden: they
+/// are empty in `round`, whose other cards are the ones of the round.
+#[derive(Clone)]
+pub struct Observation {
+    pub player: Player,
+    pub round: RoundState,
+    /// The cards of the opponent hand and of the stock.
+    pub unseen: CardSet,
+    pub opponent_hand_len: usize,
+}
+
+impl Observation {
+    /// A round consistent with the observation, the unseen cards being dealt at random
+    /// to the opponent hand and to the stock.
+    pub fn sample_world<R: Rng + ?Sized>(&self, rng: &mut R) -> RoundState {
+        let mut unseen: Vec<_> = self.unseen.iter().collect();
+        unseen.shuffle(rng);
+        let mut round = self.round.clone();
+        round.hand[self.player.opponent()] = unseen.split_off(unseen.len() - self.opponent_hand_len).into_iter().collect();
+        round.stock = unseen;
+        round
+    }
+}
+
+/// Settings of a match.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    pub rules: RuleSet,
+    pub init_point: i32,
+    pub init_dealer: Player,
+    pub player_name: [String; 2],
+    /// Seed of the deals, the same seed and actions replay the same match.
+    pub seed: u64,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            rules: RuleSet::default(),
+            init_point: DEFAULT_INIT_POINT,
+            init_dealer: Player::First,
+            player_name: ["Player 1".to_string(), "Player 2".to_string()],
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct GameState {
-    pub round_total: usize,
-    pub init_point: usize,
-    pub init_dealer: usize,        
-    pub player_name: usize,
+    pub rules: RuleSet,
+    pub init_point: i32,
+    pub init_dealer: Player,
+    pub player_name: [String; 2],
     pub round_state: RoundState,
     pub round: usize,
-    pub points: [usize; 2], 
+    pub points: [i32; 2],
     pub game_over: bool,
-    pub winner: Option<usize>
+    pub winner: Option<Player>,
+    rng: ChaCha8Rng,
 }
 
+impl GameState {
+    pub fn new(config: GameConfig) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        let round_state = RoundState::new(config.init_dealer, config.rules, &mut rng);
+        Self {
+            rules: config.rules,
+            init_point: config.init_point,
+            init_dealer: config.init_dealer,
+            player_name: config.player_name,
+            round_state,
+            round: 1,
+            points: [config.init_point; 2],
+            game_over: false,
+            winner: None,
+            rng,
+        }
+    }
+
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.game_over {
+            vec!()
+        } else {
+            self.round_state.legal_actions()
+        }
+    }
+
+    /// What `player` knows of the game, see [`Observation`].
+    pub fn observation(&self, player: Player) -> GameObservation {
+        GameObservation {
+            rules: self.rules,
+            init_point: self.init_point,
+            init_dealer: self.init_dealer,
+            player_name: self.player_name.clone(),
+            round_state: self.round_state.observation(player),
+            round: self.round,
+            points: self.points,
+            game_over: self.game_over,
+            winner: self.winner,
+        }
+    }
+
+    /// Plays `action` in the current round. When it ends the round, its points are
+    /// transferred and the next round is dealt, unless the game is over.
+    pub fn step(&mut self, action: Action) -> Result<(), GameError> {
+        if self.game_over {
+            return Err(GameError::GameOver);
+        }
+        self.round_state.apply(action)?;
+        if self.round_state.state == State::RoundOver {
+            self.end_round();
+        }
+        Ok(())
+    }
+
+    /// Replaces the cards of the current round, which must not have started yet.
+    pub fn redeal(&mut self, deal: &Deal) {
+        self.round_state = RoundState::from_deal(self.round_state.dealer, self.rules, deal);
+    }
+
+    fn end_round(&mut self) {
+        for player in Player::BOTH {
+            self.points[player] += self.round_state.round_points(player).unwrap_or(0);
+        }
+        if self.round >= self.rules.round_total || self.points.iter().any(|&point| point <= 0) {
+            self.game_over = true;
+            self.winner = match self.points[0].cmp(&self.points[1]) {
+                Ordering::Greater => Some(Player::First),
+                Ordering::Less => Some(Player::Second),
+                Ordering::Equal => None,
+            };
+        } else {
+            // the winner of a round deals the next one
+            let dealer = self.round_state.winner.unwrap_or(self.round_state.dealer);
+            self.round += 1;
+            self.round_state = RoundState::new(dealer, self.rules, &mut self.rng);
+        }
+    }
+}
+
+/// What a player knows of a game, the current round being seen through an [`Observation`].
+#[derive(Clone)]
+pub struct GameObservation {
+    pub rules: RuleSet,
+    pub init_point: i32,
+    pub init_dealer: Player,
+    pub player_name: [String; 2],
+    pub round_state: Observation,
+    pub round: usize,
+    pub points: [i32; 2],
+    pub game_over: bool,
+    pub winner: Option<Player>,
+}
+
+impl GameObservation {
+    /// The player who observes the game.
+    pub fn player(&self) -> Player {
+        self.round_state.player
+    }
+
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.game_over || self.round_state.round.turn_player() != self.player() {
+            vec!()
+        } else {
+            self.round_state.round.legal_actions()
+        }
+    }
+
+    /// A game consistent with the observation, see [`Observation::sample_world`].
+    /// The next rounds are dealt from a seed drawn from `rng`.
+    pub fn sample_world<R: Rng + ?Sized>(&self, rng: &mut R) -> GameState {
+        GameState {
+            rules: self.rules,
+            init_point: self.init_point,
+            init_dealer: self.init_dealer,
+            player_name: self.player_name.clone(),
+            round_state: self.round_state.sample_world(rng),
+            round: self.round,
+            points: self.points,
+            game_over: self.game_over,
+            winner: self.winner,
+            rng: ChaCha8Rng::seed_from_u64(rng.gen()),
+        }
+    }
+}
@@ -1,35 +1,25 @@
-use std::{array::from_fn, collections::HashSet};
+use std::array::from_fn;
 
 use ndarray::prelude::*;
 use burn::prelude::*;
-use crate::game::{Card, CARD_LIST, State, RoundState, GameState};
+use crate::game::{CardSet, CARD_LIST, State, RoundState, GameObservation, Observation};
 
-fn card_to_multi_hot(card_list: &[Card]) -> [f32; 48] {
+fn card_to_multi_hot(cards: CardSet) -> [f32; 48] {
     let mut card_multi_hot = [0f32; 48];
-    for (x, y) in card_list {
-        card_multi_hot[((x-1)*4+(y-1)) as usize] = 1f32;
+    for card in cards {
+        card_multi_hot[card.index()] = 1f32;
     }
     card_multi_hot
 }
 
-fn card_list_to_set(card_list: &[Card]) -> HashSet<Card> {
-    card_list.iter().copied().collect()
-}
-
 fn reserve_array() -> Array2<f32> {
     Array::zeros((17, 48))
 }
 
-fn inter_len(slice: &[Card], set: &HashSet<Card>) -> usize {
-    slice.iter().filter(|card| set.contains(card)).count()
-}
-
-// fn feature_tuple(x, power=[0.5,1,2], weight=[1,1,1]) {
-
+// np.abs(float(x)) ** np.array(power) * np.sign(x) * np.array(weight)
 fn feature_tuple<const N: usize>(x: f32, power: [f32; N], weight: [f32; N]) -> [f32; N] { 
-    from_fn(|i| x.powf(power[i]) * x.signum() * weight[i])
-    // todo a verifier
-    //ndarray.abs(float(x)) ** np.array(power) * np.sign(x) * np.array(weight)
+    let sign = if x == 0. { 0. } else { x.signum() };
+    from_fn(|i| x.abs().powf(power[i]) * sign * weight[i])
 }
 
 fn feature_one_hot(pos: usize, feature_length: usize) -> Vec<f32> {
@@ -38,30 +28,32 @@ fn feature_one_hot(pos: usize, feature_length: usize) -> Vec<f32> {
     x
 }
 
-fn game_status_array(state: &GameState) -> Array2<f32> {
-    let turn_player = state.round_state.turn_player();
-    let idle_player = 1 - turn_player;
+fn game_status_array(state: &GameObservation) -> Array2<f32> {
+    let round_state = &state.round_state.round;
+    let turn_player = round_state.turn_player();
+    let idle_player = turn_player.opponent();
         
     let point_diff = (state.points[turn_player] - state.points[idle_player]) as f32;
         
     let game_points = feature_tuple(point_diff/2., [0.5,1.,1.5], [1.,0.5,0.1]);
     let my_yaku_points = feature_tuple(
-            state.round_state.yaku_points(turn_player) as f32, [0.5,1.,1.5], [1.,0.5,0.1]);
+            round_state.yaku_points(turn_player) as f32, [0.5,1.,1.5], [1.,0.5,0.1]);
 
     let op_yaku_points = feature_tuple(
-            state.round_state.yaku_points(idle_player) as f32, [0.5,1.,1.5], [1.,0.5,0.1]);
+            round_state.yaku_points(idle_player) as f32, [0.5,1.,1.5], [1.,0.5,0.1]);
         
-    let round =  feature_one_hot(state.round-1, 8);
-    let turn = feature_one_hot(state.round_state.turn_16-1, 16);
-    let dealer = feature_one_hot(state.round_state.dealer-1, 2);
+    // the networks see 8 rounds, the later rounds of longer games are seen as the last one
+    let round =  feature_one_hot((state.round-1).min(7), 8);
+    let turn = feature_one_hot(round_state.turn_16-1, 16);
+    let dealer = feature_one_hot(round_state.dealer.index(), 2);
         
     let my_koikoi_num = feature_tuple(
-            state.round_state.koikoi_num(turn_player) as f32, [1.,2.], [1.,1.]);
+            round_state.koikoi_num(turn_player) as f32, [1.,2.], [1.,1.]);
     let op_koikoi_num = feature_tuple(
-            state.round_state.koikoi_num(idle_player) as f32, [1.,2.], [1.,1.]);
+            round_state.koikoi_num(idle_player) as f32, [1.,2.], [1.,1.]);
         
-    let my_koikoi = state.round_state.koikoi[turn_player].map(|x| x as f32);
-    let op_koikoi = state.round_state.koikoi[idle_player].map(|x| x as f32);
+    let my_koikoi = round_state.koikoi[turn_player].map(|x| x as f32);
+    let op_koikoi = round_state.koikoi[idle_player].map(|x| x as f32);
     
     let f_array = [
         game_points.as_slice(),
@@ -83,41 +75,30 @@ fn game_status_array(state: &GameState) -> Array2<f32> {
         .to_owned()
 }
 
-fn yaku_status_array(state: &RoundState) -> Array2<f32> {
+fn yaku_status_array(observation: &Observation) -> Array2<f32> {
+    let state = &observation.round;
     let turn_player = state.turn_player();
-    let idle_player = 1 - turn_player;
-
-    let my_hand_cards: HashSet<_> = state.hand[turn_player].iter().copied().collect();
-    let board_cards: HashSet<_> = state.field().iter().copied().collect();
-    let my_collect_cards: HashSet<_> = state.pile[turn_player].iter().copied().collect();
-    let op_collect_cards: HashSet<_> = state.pile[idle_player].iter().copied().collect();
-    let mut unseen_cards: HashSet<_> = state.hand[idle_player].iter().copied().collect();
-    for card in &state.stock {
-        unseen_cards.insert(*card);
-    };
+    let idle_player = turn_player.opponent();
+
+    let sets = [
+        state.hand[turn_player],
+        state.field,
+        state.pile[turn_player],
+        state.pile[idle_player],
+        observation.unseen,
+    ];
     let mut card_state = vec!();
-    for cards in CARD_LIST.iter() {
-        card_state.push(inter_len(cards, &my_hand_cards) as f32);
-    }
-    for cards in CARD_LIST.iter() {
-        card_state.push(inter_len(cards, &board_cards) as f32);
-    }
-    for cards in CARD_LIST.iter() {
-        card_state.push(inter_len(cards, &my_collect_cards) as f32);
-    }
-    for cards in CARD_LIST.iter() {
-        card_state.push(inter_len(cards, &op_collect_cards) as f32);
-    }
-    for cards in CARD_LIST.iter() {
-        card_state.push(inter_len(cards, &unseen_cards) as f32);
+    for set in sets {
+        for cards in CARD_LIST {
+            card_state.push((cards & set).len() as f32);
+        }
     }
 
-    let card_state: Array2<f32> = Array2::from_shape_vec((3, 1), card_state).unwrap();
-    let card_state: ArrayView2<f32> = card_state.broadcast((card_state.nrows(), 48)).unwrap();
-    let card_state: Array2<f32>  = card_state.t().to_owned();
+    let card_state: Array2<f32> = Array2::from_shape_vec((card_state.len(), 1), card_state).unwrap();
+    let card_state: Array2<f32> = card_state.broadcast((card_state.nrows(), 48)).unwrap().to_owned();
 
     let mut card_key: Vec<_> = Vec::new();
-    for cards in  CARD_LIST.iter() {
+    for cards in CARD_LIST {
         for v in card_to_multi_hot(cards) {
             card_key.push(v);
         }
@@ -134,25 +115,26 @@ pub fn suit_array() -> Array2<f32> {
     array
 }
 
-fn init_position_array(state: &RoundState) -> Array2<f32> {
+fn init_position_array(observation: &Observation) -> Array2<f32> {
+    let state = &observation.round;
     let turn_player = state.turn_player();
-    let cards_in_my_hand = card_to_multi_hot(&state.hand[turn_player]);
-    let cards_in_board = card_to_multi_hot(&state.init_board);
-    //let cards_in_board = card_to_multi_hot(self.log['basic']['initBoard'])
-    let unseen_cards = card_to_multi_hot(&state.unseen_cards(turn_player));
-    // todo
-    ndarray::stack!(Axis(0), cards_in_my_hand, unseen_cards)
+    let cards_in_my_hand = card_to_multi_hot(state.hand[turn_player]);
+    // card_to_multi_hot(self.log['basic']['initBoard'])
+    let cards_in_board = card_to_multi_hot(state.init_board);
+    let unseen_cards = card_to_multi_hot(observation.unseen);
+    ndarray::stack!(Axis(0), cards_in_my_hand, cards_in_board, unseen_cards)
 }
 
-fn current_position_array(state: &RoundState) -> Array2<f32> {
+fn current_position_array(observation: &Observation) -> Array2<f32> {
+    let state = &observation.round;
     let turn_player = state.turn_player();
-    let cards_in_my_hand = card_to_multi_hot(&state.hand[turn_player]);
-    let cards_in_my_collect = card_to_multi_hot(&state.pile[turn_player]);
-    let cards_in_board = card_to_multi_hot(&state.field());
+    let cards_in_my_hand = card_to_multi_hot(state.hand[turn_player]);
+    let cards_in_my_collect = card_to_multi_hot(state.pile[turn_player]);
+    let cards_in_board = card_to_multi_hot(state.field);
     // Bug Confirmed, for supporting the trained models, keep it as is
     // f_dict['CardInOpCollect'] = card_to_multi_hot(self.pile[self.idle_player])
-    let cards_in_op_collect = card_to_multi_hot(&state.pile[turn_player]);
-    let unseen_cards = card_to_multi_hot(&state.unseen_cards(turn_player));
+    let cards_in_op_collect = card_to_multi_hot(state.pile[turn_player]);
+    let unseen_cards = card_to_multi_hot(observation.unseen);
     ndarray::stack!(
         Axis(0),
         cards_in_my_hand,
@@ -166,9 +148,9 @@ fn current_position_array(state: &RoundState) -> Array2<f32> {
 fn pairing_state_array(state: &RoundState) -> Array2<f32> {
     let (showed_cards, paired_cards) = 
         if state.state == State::DiscardPick || state.state == State::DrawPick {
-            (card_to_multi_hot(&state.show), card_to_multi_hot(&state.pairing_cards()))
+            (card_to_multi_hot(state.show.iter().copied().collect()), card_to_multi_hot(state.pairing_cards()))
         } else {
-            (card_to_multi_hot(&[]), card_to_multi_hot(&[]))
+            (card_to_multi_hot(CardSet::EMPTY), card_to_multi_hot(CardSet::EMPTY))
         };
     ndarray::stack!(Axis(0), showed_cards, paired_cards)
 }
@@ -180,8 +162,7 @@ fn log_array(state: &RoundState) -> Array2<f32> {
     }
     let mut arr = vec!();
     for i in turn_list {
-        // todo: maybe i -1
-        for f in state.card_log[i-1] {
+        for f in state.card_log[i] {
             arr.push(f);
         }
     }
@@ -192,22 +173,34 @@ fn log_array(state: &RoundState) -> Array2<f32> {
     //np.vstack([f for turn in turn_list for _,f in self.card_log_dict[i].items()])   
 }
 
-pub fn feature_tensor<B: Backend>(state: &GameState, device: &Device<B>) -> Tensor<B, 3> {
-    let f = ndarray::concatenate![
+/// Number of feature rows of a state, each row has one column per card.
+pub const FEATURE_ROWS: usize = 300;
+
+/// Features of a game seen by its turn player, who must be the player of `state`, a (300, 48) array.
+pub fn feature_array(state: &GameObservation) -> Array2<f32> {
+    let observation = &state.round_state;
+    ndarray::concatenate![
         Axis(0),
         reserve_array(),
         game_status_array(state),
-        yaku_status_array(&state.round_state),
+        yaku_status_array(observation),
         suit_array(),
-        init_position_array(&state.round_state),
-        current_position_array(&state.round_state),
-        pairing_state_array(&state.round_state),
-        log_array(&state.round_state)
-    ];
-    let (dimx, dimy) = f.dim();
-    let flat_arr: Vec<f32> = f
-        .outer_iter().flat_map(|row| row.to_vec())
-        .collect();
-    Tensor::<B,1>::from_data(flat_arr.as_slice(), &device)
-        .reshape([1, dimx, dimy])
-}
\ No newline at end of file
+        init_position_array(observation),
+        current_position_array(observation),
+        pairing_state_array(&observation.round),
+        log_array(&observation.round)
+    ]
+}
+
+/// Features of a batch of states, a [batch, 300, 48] tensor.
+pub fn feature_batch<B: Backend>(states: &[&GameObservation], device: &Device<B>) -> Tensor<B, 3> {
+    let mut data: Vec<f32> = Vec::with_capacity(states.len() * FEATURE_ROWS * 48);
+    for state in states {
+        data.extend(feature_array(state).iter());
+    }
+    Tensor::from_data(TensorData::new(data, [states.len(), FEATURE_ROWS, 48]), device)
+}
+
+pub fn feature_tensor<B: Backend>(state: &GameObservation, device: &Device<B>) -> Tensor<B, 3> {
+    feature_batch(&[state], device)
+}
@@ -1,3 +1,13 @@
+pub mod agent;
+pub mod arena;
+pub mod cli;
 pub mod game;
 pub mod game_tensor;
-pub mod model;
\ No newline at end of file
+pub mod mcts;
+pub mod model;
+pub mod python_log;
+pub mod rating;
+pub mod record;
+pub mod reinforce;
+pub mod rules;
+pub mod train;
\ No newline at end of file
@@ -1,9 +1,5 @@
-use burn::prelude::*;
 use burn::backend::candle::{Candle, CandleDevice};
 use burn::tensor::Tensor;
-//use safetensors::SafeTensors;
-use burn_import::pytorch::{LoadArgs, PyTorchFileRecorder};
-use burn::record::{FullPrecisionSettings, Recorder};
 use rust_burn_test::model::*;
 use rust_burn_test::game_tensor::*;
 
@@ -16,36 +12,9 @@ fn main() {
     //let device = NdArrayDevice::default();
     
 
-    //let tensor_data =
-    //    std::fs::read("tensors/pick_sl.safetensors").expect("Erreur lors du chargement du fichier");
-
-    /* 
-    // Lire les tenseurs
-    let tensors =
-        SafeTensors::deserialize(&tensor_data).expect("Erreur lors de la désérialisation");
-
-    // Accéder aux données du tenseur
-    for (name, tensor) in tensors.tensors() {
-        println!("Tenseur: {}", name);
-        println!("{:?}", tensor.shape().to_vec());
-        let data: Vec<f32> = match tensor.dtype() {
-            safetensors::tensor::Dtype::F32 => tensor
-                .data()
-                .chunks(4)
-                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
-                .collect(),
-            _ => panic!("Type de donnée non supporté"),
-        };
-        println!("{}", data.len());
-    }
-    */
     println!("loading pick_sl.pt");
-    let load_args = LoadArgs::new("./tensors/pick_sl.pt".into()).with_debug_print();
-    let record = PyTorchFileRecorder::<FullPrecisionSettings>::new()
-        .load(load_args, &device)
+    let pick_model: PickModel<B> = PickModel::load_pytorch("./tensors/pick_sl.pt", &device)
         .expect("Should decode state successfully");
-    println!("creating the model");
-    let pick_model: PickModel<B> = PickModel::new(&device).load_record(record);
     let t = Tensor::zeros([1, 300, 48], &device);
     println!("forward");
     println!("dims {:?}", pick_model.forward(t).dims());
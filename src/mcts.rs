@@ -0,0 +1,217 @@
+use burn::prelude::Backend;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::agent::{sample, Agent, NeuralAgent};
+use crate::game::{Action, GameObservation, GameState, Player};
+
+/// Probabilities of the legal actions of the turn player, used by [`Ismcts`] as priors
+/// and to play the rollouts.
+pub trait Policy {
+    fn policy(&self, state: &GameObservation) -> Vec<(Action, f32)>;
+
+    /// The policy of the turn player of a sampled world. It is computed from the observation
+    /// of the player, the policies not reading the observation can skip building it.
+    fn world_policy(&self, world: &GameState) -> Vec<(Action, f32)> {
+        self.policy(&world.observation(world.round_state.turn_player()))
+    }
+}
+
+/// Gives the same probability to each legal action.
+pub struct UniformPolicy;
+
+impl UniformPolicy {
+    fn uniform(actions: Vec<Action>) -> Vec<(Action, f32)> {
+        let p = 1. / actions.len() as f32;
+        actions.into_iter().map(|action| (action, p)).collect()
+    }
+}
+
+impl Policy for UniformPolicy {
+    fn policy(&self, state: &GameObservation) -> Vec<(Action, f32)> {
+        Self::uniform(state.legal_actions())
+    }
+
+    fn world_policy(&self, world: &GameState) -> Vec<(Action, f32)> {
+        Self::uniform(world.legal_actions())
+    }
+}
+
+/// The probabilities of the networks, with the sampling of the agent.
+impl<B: Backend> Policy for NeuralAgent<B> {
+    fn policy(&self, state: &GameObservation) -> Vec<(Action, f32)> {
+        self.policy_batch(&[state]).remove(0)
+    }
+}
+
+/// Settings of the search.
+#[derive(Clone, Debug)]
+pub struct IsmctsConfig {
+    /// Determinizations searched for each decision.
+    pub iterations: usize,
+    /// Weight of the priors in the selection of the actions.
+    pub exploration: f32,
+    /// The points of a round are divided by this scale to give the values of the search.
+    pub value_scale: f32,
+}
+
+impl Default for IsmctsConfig {
+    fn default() -> Self {
+        Self { iterations: 500, exploration: 1.5, value_scale: 10. }
+    }
+}
+
+/// A node of the search tree, reached from its parent by `action` of `player`.
+struct Node {
+    action: Action,
+    player: Player,
+    prior: f32,
+    visits: u32,
+    /// Number of visits of the parent where the action was legal.
+    availability: u32,
+    /// Sum of the values for `player`.
+    value: f32,
+    children: Vec<usize>,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(action: Action, player: Player, prior: f32) -> Self {
+        Self { action, player, prior, visits: 0, availability: 0, value: 0., children: vec!(), expanded: false }
+    }
+}
+
+/// Information set Monte Carlo tree search over the actions of the current round.
+///
+/// Each iteration samples a world consistent with the observation of the turn player, see
+/// [`GameObservation::sample_world`], and descends a single tree shared by all these worlds,
+/// selecting the actions by their priors and values (PUCT) among the legal ones.
+/// Rollouts play the round to its end, whose points are the values.
+pub struct Ismcts {
+    config: IsmctsConfig,
+    prior: Box<dyn Policy>,
+    rollout: Box<dyn Policy>,
+    rng: ChaCha8Rng,
+}
+
+impl Ismcts {
+    /// Searches with uniform priors and random rollouts.
+    pub fn new(config: IsmctsConfig) -> Self {
+        Self {
+            config,
+            prior: Box::new(UniformPolicy),
+            rollout: Box::new(UniformPolicy),
+            rng: ChaCha8Rng::seed_from_u64(0),
+        }
+    }
+
+    /// The policy giving the priors of the actions, such as a [`NeuralAgent`].
+    pub fn with_prior(mut self, prior: Box<dyn Policy>) -> Self {
+        self.prior = prior;
+        self
+    }
+
+    /// The policy playing the rollouts.
+    pub fn with_rollout(mut self, rollout: Box<dyn Policy>) -> Self {
+        self.rollout = rollout;
+        self
+    }
+
+    /// Seeds the determinizations and the rollouts.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
+
+    /// Visits of each legal action of the turn player after the search.
+    pub fn search(&mut self, state: &GameObservation) -> Vec<(Action, u32)> {
+        let mut nodes = vec![Node::new(Action::Continue, state.player().opponent(), 1.)];
+        for _ in 0..self.config.iterations {
+            let mut world = state.sample_world(&mut self.rng);
+            let mut path = vec![0];
+            let mut node = 0;
+            while !round_over(state, &world) {
+                if !nodes[node].expanded {
+                    self.expand(&mut nodes, node, &world);
+                    break;
+                }
+                node = self.select(&mut nodes, node, &world);
+                world.step(nodes[node].action).expect("the search plays legal actions");
+                path.push(node);
+            }
+            while !round_over(state, &world) {
+                let policy = self.rollout.world_policy(&world);
+                world.step(sample(&policy, &mut self.rng)).expect("the rollout policy plays legal actions");
+            }
+            for &node in &path {
+                let player = nodes[node].player;
+                nodes[node].visits += 1;
+                nodes[node].value += (world.points[player] - state.points[player]) as f32 / self.config.value_scale;
+            }
+        }
+        nodes[0].children.iter().map(|&child| (nodes[child].action, nodes[child].visits)).collect()
+    }
+
+    fn expand(&self, nodes: &mut Vec<Node>, node: usize, world: &GameState) {
+        let player = world.round_state.turn_player();
+        let policy = match world.legal_actions()[..] {
+            [action] => vec![(action, 1.)],
+            _ => self.prior.world_policy(world),
+        };
+        for (action, prior) in policy {
+            add_child(nodes, node, Node::new(action, player, prior));
+        }
+        nodes[node].expanded = true;
+    }
+
+    /// The child of `node` maximizing the PUCT score among the legal actions of `world`,
+    /// the actions legal in no previous determinization are added with a uniform prior.
+    fn select(&self, nodes: &mut Vec<Node>, node: usize, world: &GameState) -> usize {
+        let player = world.round_state.turn_player();
+        let legal_actions = world.legal_actions();
+        let mut legal_children = vec!();
+        for &action in &legal_actions {
+            let child = match nodes[node].children.iter().find(|&&child| nodes[child].action == action) {
+                Some(&child) => child,
+                None => add_child(nodes, node, Node::new(action, player, 1. / legal_actions.len() as f32)),
+            };
+            nodes[child].availability += 1;
+            legal_children.push(child);
+        }
+        let score = |child: usize| {
+            let Node { prior, visits, availability, value, .. } = nodes[child];
+            let q = if visits == 0 { 0. } else { value / visits as f32 };
+            q + self.config.exploration * prior * (availability as f32).sqrt() / (1 + visits) as f32
+        };
+        legal_children
+            .into_iter()
+            .max_by(|&a, &b| score(a).total_cmp(&score(b)))
+            .expect("no legal action")
+    }
+}
+
+fn add_child(nodes: &mut Vec<Node>, parent: usize, child: Node) -> usize {
+    nodes.push(child);
+    let child = nodes.len() - 1;
+    nodes[parent].children.push(child);
+    child
+}
+
+/// Whether the round of `state` is over in `world`, the next round being already dealt.
+fn round_over(state: &GameObservation, world: &GameState) -> bool {
+    world.game_over || world.round != state.round
+}
+
+impl Agent for Ismcts {
+    /// Plays the most visited action.
+    fn act(&mut self, state: &GameObservation) -> Action {
+        if let [action] = state.legal_actions()[..] {
+            return action;
+        }
+        self.search(state)
+            .into_iter()
+            .max_by_key(|&(_, visits)| visits)
+            .expect("no legal action")
+            .0
+    }
+}
@@ -1,23 +1,83 @@
+use std::{collections::HashMap, fmt, path::{Path, PathBuf}};
+
+use burn::module::ParamId;
 use burn::nn::{
+    attention::{MhaInput, MultiHeadAttention, MultiHeadAttentionConfig},
     conv::{Conv1d, Conv1dConfig},
-    transformer::{TransformerEncoder, TransformerEncoderConfig, TransformerEncoderInput},
-    Relu
+    Dropout, DropoutConfig, LayerNorm, LayerNormConfig, Linear, LinearConfig, Relu
 };
 use burn::prelude::*;
-use burn::tensor::Tensor;
-//use safetensors::SafeTensors;
+use burn::record::serde::{
+    adapter::DefaultAdapter,
+    data::{remap, unflatten, NestedValue, Serializable},
+    de::Deserializer,
+    error,
+    ser::Serializer,
+};
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder, PrecisionSettings, Record, RecorderError};
+use burn::tensor::{ElementConversion, Tensor, TensorData};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 fn layer_norm<B: Backend, const D: usize>(x: Tensor<B, D>, dim: usize, eps: f32) -> Tensor<B, D> {
     let (variance, mean) = x.clone().var_mean_bias(dim);
     (x - mean) / (variance + eps).sqrt()
 }
 
+/// The feed-forward part of an [`EncoderLayer`].
+#[derive(Module, Debug)]
+struct FeedForward<B: Backend> {
+    linear_inner: Linear<B>,
+    linear_outer: Linear<B>,
+    dropout: Dropout,
+}
+
+impl<B: Backend> FeedForward<B> {
+    fn forward(&self, x: Tensor<B, 3>) -> Tensor<B, 3> {
+        let x = self.linear_inner.forward(x);
+        let x = Relu.forward(x);
+        let x = self.dropout.forward(x);
+        self.linear_outer.forward(x)
+    }
+}
+
+/// A layer of PyTorch's `nn.TransformerEncoder` with its defaults: post-norm and a ReLU
+/// feed-forward, where the one of burn uses GELU. The modules have the names of burn's.
+#[derive(Module, Debug)]
+struct EncoderLayer<B: Backend> {
+    mha: MultiHeadAttention<B>,
+    pwff: FeedForward<B>,
+    norm_1: LayerNorm<B>,
+    norm_2: LayerNorm<B>,
+    dropout: Dropout,
+}
+
+impl<B: Backend> EncoderLayer<B> {
+    fn forward(&self, x: Tensor<B, 3>) -> Tensor<B, 3> {
+        let attention = self.mha.forward(MhaInput::self_attn(x.clone())).context;
+        let x = self.norm_1.forward(x + self.dropout.forward(attention));
+        let feed_forward = self.pwff.forward(x.clone());
+        self.norm_2.forward(x + self.dropout.forward(feed_forward))
+    }
+}
+
+#[derive(Module, Debug)]
+struct Encoder<B: Backend> {
+    layers: Vec<EncoderLayer<B>>,
+}
+
+impl<B: Backend> Encoder<B> {
+    fn forward(&self, x: Tensor<B, 3>) -> Tensor<B, 3> {
+        self.layers.iter().fold(x, |x, layer| layer.forward(x))
+    }
+}
+
 #[derive(Module, Debug)]
 struct EncoderBlock<B: Backend> {
     f1: Conv1d<B>,
     f2: Conv1d<B>,
     //layernorm: LayerNorm<B>,
-    attn_encoder: TransformerEncoder<B>,
+    attn_encoder: Encoder<B>,
 }
 
 impl<B: Backend> EncoderBlock<B> {
@@ -26,9 +86,11 @@ impl<B: Backend> EncoderBlock<B> {
         let x = Relu.forward(x);
         let x = self.f2.forward(x);
         let x = layer_norm(x, 2, 0.0001);
-        let x = x.permute([2, 0, 1]);
-        let x = self.attn_encoder.forward(TransformerEncoderInput::new(x));
-        x.permute([1, 2, 0])
+        // the PyTorch model attends over the cards of [card, batch, emb], burn over the
+        // second dimension of [batch, seq, emb]
+        let x = x.swap_dims(1, 2);
+        let x = self.attn_encoder.forward(x);
+        x.swap_dims(1, 2)
     }
 }
 
@@ -47,22 +109,113 @@ impl EncoderBlockConfig {
             f1: Conv1dConfig::new(self.n_input, self.n_fw, 1).init(device),
             f2: Conv1dConfig::new(self.n_fw, self.n_emb, 1).init(device),
             //layernorm: LayerNormConfig::new(2).init(device), // todo
-            attn_encoder: TransformerEncoderConfig::new(
-                self.n_emb,
-                self.n_fw,
-                self.n_heads,
-                self.n_layers,
-            )
-            .init(device),
+            attn_encoder: Encoder {
+                layers: (0..self.n_layers).map(|_| self.encoder_layer(device)).collect(),
+            },
+        }
+    }
+
+    fn encoder_layer<B: Backend>(&self, device: &B::Device) -> EncoderLayer<B> {
+        let dropout = 0.1;
+        EncoderLayer {
+            mha: MultiHeadAttentionConfig::new(self.n_emb, self.n_heads).with_dropout(dropout).init(device),
+            pwff: FeedForward {
+                linear_inner: LinearConfig::new(self.n_emb, self.n_fw).init(device),
+                linear_outer: LinearConfig::new(self.n_fw, self.n_emb).init(device),
+                dropout: DropoutConfig::new(dropout).init(),
+            },
+            norm_1: LayerNormConfig::new(self.n_emb).init(device),
+            norm_2: LayerNormConfig::new(self.n_emb).init(device),
+            dropout: DropoutConfig::new(dropout).init(),
+        }
+    }
+}
+
+/// Hyperparameters of the three networks, saved as JSON next to their checkpoints.
+#[derive(Config, Debug)]
+pub struct KoiKoiNetConfig {
+    /// Number of feature rows of the input, see [`crate::game_tensor::feature_tensor`].
+    #[config(default = 300)]
+    pub n_input: usize,
+    #[config(default = 256)]
+    pub n_emb: usize,
+    #[config(default = 512)]
+    pub n_fw: usize,
+    #[config(default = 4)]
+    pub n_heads: usize,
+    #[config(default = 2)]
+    pub n_layers: usize,
+}
+
+impl KoiKoiNetConfig {
+    fn encoder_block(&self) -> EncoderBlockConfig {
+        EncoderBlockConfig::new(self.n_input, self.n_emb, self.n_fw, self.n_heads, self.n_layers)
+    }
+
+    pub fn init_discard<B: Backend>(&self, device: &B::Device) -> DiscardModel<B> {
+        DiscardModel {
+            encoder_block: self.encoder_block().init(device),
+            out: Conv1dConfig::new(self.n_emb, 1, 1).init(device),
+        }
+    }
+
+    pub fn init_pick<B: Backend>(&self, device: &B::Device) -> PickModel<B> {
+        PickModel {
+            encoder_block: self.encoder_block().init(device),
+            out: Conv1dConfig::new(self.n_emb, 1, 1).init(device),
+        }
+    }
+
+    pub fn init_koikoi<B: Backend>(&self, device: &B::Device) -> KoiKoiModel<B> {
+        KoiKoiModel {
+            encoder_block: self.encoder_block().init(device),
+            out: Conv1dConfig::new(self.n_emb, 1, 1).init(device),
+        }
+    }
+
+    /// Path of the config of a checkpoint: the same file with a `.config.json` extension.
+    pub fn checkpoint_path(checkpoint: &Path) -> PathBuf {
+        checkpoint.with_extension("config.json")
+    }
+
+    /// Reads the config saved next to `checkpoint`, or the default one if there is none.
+    pub fn for_checkpoint(checkpoint: &Path) -> Result<Self, LoadError> {
+        let path = Self::checkpoint_path(checkpoint);
+        if path.exists() {
+            Self::load(&path).map_err(|err| LoadError::Config(err.to_string()))
+        } else {
+            Ok(Self::new())
         }
     }
 }
 
-const N_INPUT: usize = 300;
-const N_EMB: usize = 256;
-const N_FW: usize = 512;
-const N_HEADS: usize = 4;
-const N_LAYERS: usize = 2;
+/// Loading of the three networks, which only differ by their output layer.
+pub trait KoiKoiNet<B: Backend>: Module<B> + Sized {
+    fn init(config: &KoiKoiNetConfig, device: &B::Device) -> Self;
+
+    /// Loads a checkpoint of the original PyTorch model, see [`KoiKoiNetConfig::for_checkpoint`].
+    fn load_pytorch(path: impl Into<PathBuf>, device: &B::Device) -> Result<Self, LoadError> {
+        let path = path.into();
+        let config = KoiKoiNetConfig::for_checkpoint(&path)?;
+        let record = load_checkpoint(read_pytorch(path)?, &config, device)?;
+        Ok(Self::init(&config, device).load_record(record))
+    }
+
+    /// Loads the weights of the original PyTorch model saved as safetensors.
+    fn load_safetensors(path: impl Into<PathBuf>, device: &B::Device) -> Result<Self, LoadError> {
+        let path = path.into();
+        let config = KoiKoiNetConfig::for_checkpoint(&path)?;
+        let record = load_checkpoint(read_safetensors(path)?, &config, device)?;
+        Ok(Self::init(&config, device).load_record(record))
+    }
+
+    /// Loads weights saved as a burn record, as by [`crate::train::train`].
+    fn load_mpk(path: impl Into<PathBuf>, device: &B::Device) -> Result<Self, LoadError> {
+        let path = path.into();
+        let config = KoiKoiNetConfig::for_checkpoint(&path)?;
+        Ok(Self::init(&config, device).load_file(path, &MpkRecorder::new(), device)?)
+    }
+}
 
 #[derive(Module, Debug)]
 pub struct DiscardModel<B: Backend> {
@@ -77,12 +230,13 @@ impl<B: Backend> DiscardModel<B> {
     }
 
     pub fn new(device: &B::Device) -> Self {
-        Self {
-            encoder_block: EncoderBlockConfig::new(N_INPUT, N_EMB, N_FW, N_HEADS, N_LAYERS)
-                .init(device),
-            out: Conv1dConfig::new(N_EMB, 1, 1)
-                .init(device),
-        }
+        KoiKoiNetConfig::new().init_discard(device)
+    }
+}
+
+impl<B: Backend> KoiKoiNet<B> for DiscardModel<B> {
+    fn init(config: &KoiKoiNetConfig, device: &B::Device) -> Self {
+        config.init_discard(device)
     }
 }
 
@@ -100,12 +254,13 @@ impl<B: Backend> PickModel<B> {
     }
 
     pub fn new(device: &B::Device) -> Self {
-        Self {
-            encoder_block: EncoderBlockConfig::new(N_INPUT, N_EMB, N_FW, N_HEADS, N_LAYERS)
-                .init(device),
-            out: Conv1dConfig::new(N_EMB, 1, 1)
-                .init(device),
-        }
+        KoiKoiNetConfig::new().init_pick(device)
+    }
+}
+
+impl<B: Backend> KoiKoiNet<B> for PickModel<B> {
+    fn init(config: &KoiKoiNetConfig, device: &B::Device) -> Self {
+        config.init_pick(device)
     }
 }
 
@@ -123,11 +278,216 @@ impl<B: Backend> KoiKoiModel<B> {
     }
 
     pub fn new(device: &B::Device) -> Self {
-        Self {
-            encoder_block: EncoderBlockConfig::new(N_INPUT, N_EMB, N_FW, N_HEADS, N_LAYERS)
-                .init(device),
-            out: Conv1dConfig::new(N_EMB, 1, 1)
-                .init(device),
+        KoiKoiNetConfig::new().init_koikoi(device)
+    }
+}
+
+impl<B: Backend> KoiKoiNet<B> for KoiKoiModel<B> {
+    fn init(config: &KoiKoiNetConfig, device: &B::Device) -> Self {
+        config.init_koikoi(device)
+    }
+}
+
+/// Recorder of the weights trained in Rust.
+pub type MpkRecorder = NamedMpkFileRecorder<FullPrecisionSettings>;
+
+/// Renames the keys of the PyTorch `nn.TransformerEncoder` to the ones of burn, layer norms
+/// using `gamma`/`beta`. The `in_proj` of the attention layers is split by [`adapt_pytorch`].
+const PYTORCH_KEY_REMAP: [(&str, &str); 8] = [
+    (r"\.norm1\.", ".norm_1."),
+    (r"\.norm2\.", ".norm_2."),
+    (r"(\.norm_\d)\.weight$", "${1}.gamma"),
+    (r"(\.norm_\d)\.bias$", "${1}.beta"),
+    (r"\.self_attn\.", ".mha."),
+    (r"\.out_proj\.", ".output."),
+    (r"\.linear1\.", ".pwff.linear_inner."),
+    (r"\.linear2\.", ".pwff.linear_outer."),
+];
+
+/// Error raised when a checkpoint does not match the model.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file can not be read or decoded.
+    Read(String),
+    /// The config next to the checkpoint is invalid.
+    Config(String),
+    /// Tensors of the model absent from the checkpoint.
+    MissingKeys(Vec<String>),
+    /// Tensors of the checkpoint unknown to the model.
+    UnexpectedKeys(Vec<String>),
+    ShapeMismatch { key: String, expected: Vec<usize>, actual: Vec<usize> },
+    Record(RecorderError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Read(err) => write!(f, "failed to read the checkpoint: {err}"),
+            LoadError::Config(err) => write!(f, "invalid model config: {err}"),
+            LoadError::MissingKeys(keys) => write!(f, "missing keys: {}", keys.join(", ")),
+            LoadError::UnexpectedKeys(keys) => write!(f, "unexpected keys: {}", keys.join(", ")),
+            LoadError::ShapeMismatch { key, expected, actual } =>
+                write!(f, "shape of {key} is {actual:?}, expected {expected:?}"),
+            LoadError::Record(err) => write!(f, "failed to load the record: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<RecorderError> for LoadError {
+    fn from(err: RecorderError) -> Self {
+        LoadError::Record(err)
+    }
+}
+
+fn read_pytorch(path: PathBuf) -> Result<HashMap<String, candle_core::Tensor>, LoadError> {
+    Ok(candle_core::pickle::read_all(path)
+        .map_err(|err| LoadError::Read(err.to_string()))?
+        .into_iter()
+        .collect())
+}
+
+fn read_safetensors(path: PathBuf) -> Result<HashMap<String, candle_core::Tensor>, LoadError> {
+    candle_core::safetensors::load(path, &candle_core::Device::Cpu)
+        .map_err(|err| LoadError::Read(err.to_string()))
+}
+
+/// Keys and shapes of the tensors of a PyTorch checkpoint.
+fn pytorch_shapes(config: &KoiKoiNetConfig) -> HashMap<String, Vec<usize>> {
+    let (n_emb, n_fw) = (config.n_emb, config.n_fw);
+    let mut shapes = HashMap::new();
+    shapes.insert("encoder_block.f1.weight".to_string(), vec![n_fw, config.n_input, 1]);
+    shapes.insert("encoder_block.f1.bias".to_string(), vec![n_fw]);
+    shapes.insert("encoder_block.f2.weight".to_string(), vec![n_emb, n_fw, 1]);
+    shapes.insert("encoder_block.f2.bias".to_string(), vec![n_emb]);
+    for layer in 0..config.n_layers {
+        let layer_shapes = [
+            ("self_attn.in_proj_weight", vec![3 * n_emb, n_emb]),
+            ("self_attn.in_proj_bias", vec![3 * n_emb]),
+            ("self_attn.out_proj.weight", vec![n_emb, n_emb]),
+            ("self_attn.out_proj.bias", vec![n_emb]),
+            ("linear1.weight", vec![n_fw, n_emb]),
+            ("linear1.bias", vec![n_fw]),
+            ("linear2.weight", vec![n_emb, n_fw]),
+            ("linear2.bias", vec![n_emb]),
+            ("norm1.weight", vec![n_emb]),
+            ("norm1.bias", vec![n_emb]),
+            ("norm2.weight", vec![n_emb]),
+            ("norm2.bias", vec![n_emb]),
+        ];
+        for (key, shape) in layer_shapes {
+            shapes.insert(format!("encoder_block.attn_encoder.layers.{layer}.{key}"), shape);
         }
     }
-}
\ No newline at end of file
+    shapes.insert("out.weight".to_string(), vec![1, n_emb, 1]);
+    shapes.insert("out.bias".to_string(), vec![1]);
+    shapes
+}
+
+fn check_shapes(
+    tensors: &HashMap<String, candle_core::Tensor>,
+    config: &KoiKoiNetConfig,
+) -> Result<(), LoadError> {
+    let shapes = pytorch_shapes(config);
+    let mut missing: Vec<_> = shapes.keys().filter(|key| !tensors.contains_key(*key)).cloned().collect();
+    if !missing.is_empty() {
+        missing.sort();
+        return Err(LoadError::MissingKeys(missing));
+    }
+    let mut unexpected: Vec<_> = tensors.keys().filter(|key| !shapes.contains_key(*key)).cloned().collect();
+    if !unexpected.is_empty() {
+        unexpected.sort();
+        return Err(LoadError::UnexpectedKeys(unexpected));
+    }
+    for (key, tensor) in tensors {
+        if tensor.dims() != shapes[key] {
+            return Err(LoadError::ShapeMismatch {
+                key: key.clone(),
+                expected: shapes[key].clone(),
+                actual: tensor.dims().to_vec(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Builds the record of a model from the tensors of a PyTorch state dict.
+fn load_checkpoint<B: Backend, R: Record<B>>(
+    tensors: HashMap<String, candle_core::Tensor>,
+    config: &KoiKoiNetConfig,
+    device: &B::Device
+) -> Result<R, LoadError> {
+    check_shapes(&tensors, config)?;
+    let tensors = adapt_pytorch(tensors).map_err(|err| LoadError::Read(err.to_string()))?;
+    let key_remap = PYTORCH_KEY_REMAP
+        .iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid key pattern"), replacement.to_string()))
+        .collect();
+    let tensors = tensors
+        .into_iter()
+        .map(|(key, tensor)| (key, CandleTensor(tensor)))
+        .collect();
+    let (tensors, _) = remap(tensors, key_remap);
+    let nested_value = unflatten::<FullPrecisionSettings, _>(tensors)
+        .map_err(|err| RecorderError::DeserializeError(err.to_string()))?;
+    let deserializer = Deserializer::<DefaultAdapter>::new(nested_value, true);
+    let item = R::Item::<FullPrecisionSettings>::deserialize(deserializer)
+        .map_err(|err| RecorderError::DeserializeError(err.to_string()))?;
+    Ok(R::from_item(item, device))
+}
+
+struct CandleTensor(candle_core::Tensor);
+
+impl Serializable for CandleTensor {
+    fn serialize<PS: PrecisionSettings>(&self, serializer: Serializer) -> Result<NestedValue, error::Error> {
+        let to_error = |err: candle_core::Error| error::Error::Other(err.to_string());
+        let data: Vec<PS::FloatElem> = self.0
+            .to_dtype(candle_core::DType::F32)
+            .and_then(|tensor| tensor.flatten_all())
+            .and_then(|tensor| tensor.to_vec1::<f32>())
+            .map_err(to_error)?
+            .into_iter()
+            .map(ElementConversion::elem)
+            .collect();
+        let TensorData { bytes, shape, dtype } = TensorData::new(data, self.0.dims().to_vec());
+
+        let mut tensor_data = HashMap::new();
+        tensor_data.insert("bytes".to_string(), NestedValue::U8s(bytes));
+        tensor_data.insert("shape".to_string(), shape.serialize(serializer.clone())?);
+        tensor_data.insert("dtype".to_string(), dtype.serialize(serializer)?);
+
+        let mut param = HashMap::new();
+        param.insert("id".to_string(), NestedValue::String(ParamId::new().into_string()));
+        param.insert("param".to_string(), NestedValue::Map(tensor_data));
+        Ok(NestedValue::Map(param))
+    }
+}
+
+/// Adapts the tensors of PyTorch to burn: the packed `in_proj` of the attention layers is
+/// split into the query, key and value linear layers, and linear weights are transposed.
+fn adapt_pytorch(
+    tensors: HashMap<String, candle_core::Tensor>,
+) -> candle_core::Result<HashMap<String, candle_core::Tensor>> {
+    let mut adapted = HashMap::new();
+    for (key, tensor) in tensors {
+        let parts = match key.rsplit_once(".in_proj_") {
+            Some((prefix, kind)) => ["query", "key", "value"]
+                .into_iter()
+                .zip(tensor.chunk(3, 0)?)
+                .map(|(name, part)| (format!("{prefix}.{name}.{kind}"), part))
+                .collect(),
+            None => vec![(key, tensor)],
+        };
+        for (key, tensor) in parts {
+            // the convolutions have 3 dimensions and are kept as they are
+            let tensor = if key.ends_with(".weight") && tensor.rank() == 2 {
+                tensor.t()?.contiguous()?
+            } else {
+                tensor
+            };
+            adapted.insert(key, tensor);
+        }
+    }
+    Ok(adapted)
+}
@@ -0,0 +1,300 @@
+//! Imports the logs of the Python KoiKoi-AI environment as [`GameRecord`]s, replaying them
+//! through the engine and stopping at the first divergence.
+//!
+//! A game log is an object `{"round1": <round>, "round2": ...}` or a list of rounds, a single
+//! round is also accepted. A round is the log dictionary of a Python `RoundState` next to its
+//! card log and koi-koi calls, players and dealers being numbered from 1:
+//!
+//! ```json
+//! {
+//!   "basic": {"dealer": 1, "initHand1": [[1, 1], ...], "initHand2": [...], "initBoard": [...]},
+//!   "card_log_dict": {"1": {"discard": [48 numbers], "discardPairing": [...], ...}, ..., "16": {...}},
+//!   "koikoi": {"1": [0, 0, 0, 0, 0, 0, 0, 0], "2": [...]},
+//!   "winner": 2
+//! }
+//! ```
+//!
+//! The rows of a turn of `card_log_dict` are read by their names, see [`ROW_KEYS`]. Lists may
+//! replace the objects keyed by turn, by player or by row, the rows being then in the order
+//! of [`CardLog`], and `winner` is optional.
+//! The stock is rebuilt from the drawn cards, so the draws always match.
+//!
+//! Logs written by the Python environment go in `tests/fixtures/koikoi_ai` and are imported by
+//! the ignored test `python_logs_are_imported`.
+
+use std::{fmt, fs, path::Path};
+use serde_json::Value;
+
+use crate::game::{
+    Action, Card, CardLog, Deal, GameConfig, GameState, Player, RoundState, State,
+    LOG_DISCARD, LOG_DRAW, LOG_PICK,
+};
+use crate::record::GameRecord;
+
+/// Keys of the rows of a turn of `card_log_dict`, in the order of [`CardLog`].
+pub const ROW_KEYS: [&str; 8] = [
+    "discard", "discardPairing", "discardPick", "discardCollect",
+    "draw", "drawPairing", "drawPick", "drawCollect",
+];
+
+/// Where and why a log can not be imported.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ImportError {
+    /// Round of the divergence, 0 if the log can not be read at all.
+    pub round: usize,
+    pub turn: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.round, self.turn) {
+            (0, _) => write!(f, "{}", self.message),
+            (round, None) => write!(f, "round {round}: {}", self.message),
+            (round, Some(turn)) => write!(f, "round {round}, turn {turn}: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+struct RoundLog {
+    dealer: Player,
+    hands: [Vec<Card>; 2],
+    board: Vec<Card>,
+    card_log: CardLog,
+    koikoi: [[i32; 8]; 2],
+    winner: Option<Player>,
+}
+
+/// Reads and imports the game log at `path`, see [`import_game`].
+pub fn import_file(path: &Path, config: &GameConfig) -> Result<GameRecord, ImportError> {
+    let error = |message: String| ImportError { round: 0, turn: None, message };
+    let json = fs::read_to_string(path).map_err(|err| error(format!("{}: {err}", path.display())))?;
+    let log = serde_json::from_str(&json).map_err(|err| error(format!("{}: {err}", path.display())))?;
+    import_game(&log, config)
+}
+
+/// Imports a game log, the rounds, the initial points and the player names are taken from
+/// `config`. The first dealer is the one of the first round.
+pub fn import_game(log: &Value, config: &GameConfig) -> Result<GameRecord, ImportError> {
+    let rounds = rounds(log).map_err(|message| ImportError { round: 0, turn: None, message })?;
+    let mut record = GameRecord::new(config);
+    let mut game: Option<GameState> = None;
+    for (i, round) in rounds.iter().enumerate() {
+        let number = i + 1;
+        let error = |turn, message| ImportError { round: number, turn, message };
+        let round = parse_round(round).map_err(|message| error(None, message))?;
+        let game = game.get_or_insert_with(|| {
+            record.dealer = round.dealer;
+            GameState::new(record.config())
+        });
+        if game.game_over {
+            return Err(error(None, "the game is already over".to_string()));
+        }
+        if game.round_state.dealer != round.dealer {
+            let message = format!("dealt by the {} instead of the {}", round.dealer, game.round_state.dealer);
+            return Err(error(None, message));
+        }
+        let deal = round.deal().map_err(|message| error(None, message))?;
+        game.redeal(&deal);
+        record.deals.push(deal);
+
+        let points = game.points;
+        while game.round == number && !game.game_over {
+            let state = &game.round_state;
+            let turn = state.turn_16;
+            check_turn(state, &round.card_log, state.state == State::KoiKoi)
+                .map_err(|message| error(Some(turn), message))?;
+            let action = round.action(state).map_err(|message| error(Some(turn), message))?;
+            record.push(game, action);
+            game.step(action).map_err(|err| error(Some(turn), format!("{action:?} is rejected: {err}")))?;
+        }
+
+        let winner = Player::BOTH.into_iter().find(|&player| game.points[player] > points[player]);
+        if let (Some(expected), Some(actual)) = (round.winner, winner) {
+            if expected != actual {
+                let message = format!("won by the {actual} instead of the {expected}");
+                return Err(error(None, message));
+            }
+        }
+    }
+    Ok(record)
+}
+
+/// The logged rows of the current turn must match the rows written by the engine,
+/// all of them once the turn is `complete`, else the ones written so far.
+fn check_turn(state: &RoundState, card_log: &CardLog, complete: bool) -> Result<(), String> {
+    let turn = state.turn_16 - 1;
+    for (row, (expected, actual)) in card_log[turn].iter().zip(&state.card_log[turn]).enumerate() {
+        let written = actual.iter().any(|&x| x != 0.);
+        if (complete || written) && expected != actual {
+            let name = row_name(row);
+            return Err(format!("{name} is {:?} instead of {:?}", cards_of(actual), cards_of(expected)));
+        }
+    }
+    Ok(())
+}
+
+/// The key of a row in words, `discard pairing` for `discardPairing`.
+fn row_name(row: usize) -> String {
+    ROW_KEYS[row].chars().fold(String::new(), |mut name, c| {
+        if c.is_ascii_uppercase() {
+            name.push(' ');
+        }
+        name.push(c.to_ascii_lowercase());
+        name
+    })
+}
+
+fn cards_of(row: &[f32; 48]) -> Vec<Card> {
+    (0..48).filter(|&i| row[i] != 0.).map(Card::from_index).collect()
+}
+
+impl RoundLog {
+    /// The only card of a row of the current turn.
+    fn card(&self, state: &RoundState, row: usize) -> Result<Card, String> {
+        match cards_of(&self.card_log[state.turn_16 - 1][row])[..] {
+            [card] => Ok(card),
+            ref cards => Err(format!("expected one card in the {} row, found {cards:?}", row_name(row))),
+        }
+    }
+
+    fn action(&self, state: &RoundState) -> Result<Action, String> {
+        let legal_actions = state.legal_actions();
+        if let [action] = legal_actions[..] {
+            return Ok(action);
+        }
+        Ok(match state.state {
+            State::Discard => Action::Discard(self.card(state, LOG_DISCARD)?),
+            State::DiscardPick => Action::Pick(self.card(state, LOG_DISCARD + LOG_PICK)?),
+            State::DrawPick => Action::Pick(self.card(state, LOG_DRAW + LOG_PICK)?),
+            State::KoiKoi => Action::KoiKoi(self.koikoi[state.turn_player()][state.turn_16.div_ceil(2) - 1] != 0),
+            _ => return Err(format!("no action in state {:?}", state.state)),
+        })
+    }
+
+    /// The dealt cards, the stock holds the drawn cards last and the other ones sorted.
+    fn deal(&self) -> Result<Deal, String> {
+        let drawn: Vec<_> = self.card_log
+            .iter()
+            .map(|turn| cards_of(&turn[LOG_DRAW]))
+            .take_while(|cards| cards.len() == 1)
+            .map(|cards| cards[0])
+            .collect();
+        let mut seen = [false; 48];
+        for &card in self.hands.iter().flatten().chain(&self.board).chain(&drawn) {
+            if std::mem::replace(&mut seen[card.index()], true) {
+                return Err(format!("card {card:?} is dealt twice"));
+            }
+        }
+        let mut stock: Vec<_> = (0..48).filter(|&i| !seen[i]).map(Card::from_index).collect();
+        stock.extend(drawn.iter().rev());
+        Ok(Deal { hands: self.hands.clone(), field: self.board.clone(), stock })
+    }
+}
+
+fn rounds(log: &Value) -> Result<Vec<&Value>, String> {
+    match log {
+        Value::Array(rounds) => Ok(rounds.iter().collect()),
+        Value::Object(map) if map.contains_key("basic") => Ok(vec![log]),
+        Value::Object(map) => {
+            let mut rounds: Vec<_> = map
+                .iter()
+                .filter_map(|(key, round)| Some((key.strip_prefix("round")?.parse::<usize>().ok()?, round)))
+                .collect();
+            rounds.sort_by_key(|&(number, _)| number);
+            if rounds.is_empty() {
+                return Err("no round in the log".to_string());
+            }
+            Ok(rounds.into_iter().map(|(_, round)| round).collect())
+        }
+        _ => Err("the log is not an object or a list".to_string()),
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, String> {
+    value.get(key).ok_or_else(|| format!("missing {key}"))
+}
+
+fn number(value: &Value) -> Result<f64, String> {
+    value.as_f64().ok_or_else(|| format!("{value} is not a number"))
+}
+
+fn card(value: &Value) -> Result<Card, String> {
+    match value.as_array().map(|pair| pair.iter().map(number).collect::<Result<Vec<_>, _>>()) {
+        Some(Ok(pair)) if pair.len() == 2 && (1. ..=12.).contains(&pair[0]) && (1. ..=4.).contains(&pair[1]) =>
+            Ok(Card::new(pair[0] as u8, pair[1] as u8)),
+        _ => Err(format!("{value} is not a card")),
+    }
+}
+
+fn cards(value: &Value) -> Result<Vec<Card>, String> {
+    value.as_array().ok_or_else(|| format!("{value} is not a list of cards"))?.iter().map(card).collect()
+}
+
+/// The values of a list, or of an object keyed by `keys`.
+fn items(value: &Value, keys: impl IntoIterator<Item = String>) -> Result<Vec<&Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items.iter().collect()),
+        Value::Object(map) => keys.into_iter().map(|key| map.get(&key).ok_or_else(|| format!("missing {key}"))).collect(),
+        _ => Err("expected a list or an object".to_string()),
+    }
+}
+
+/// The values of a list, or of an object keyed by the numbers from 1 to `len`.
+fn numbered(value: &Value, len: usize) -> Result<Vec<&Value>, String> {
+    items(value, (1..=len).map(|i| i.to_string()))
+}
+
+fn player(value: &Value) -> Result<Player, String> {
+    match number(value)? {
+        1. => Ok(Player::First),
+        2. => Ok(Player::Second),
+        _ => Err(format!("{value} is not a player")),
+    }
+}
+
+fn parse_round(round: &Value) -> Result<RoundLog, String> {
+    let basic = field(round, "basic")?;
+    let mut card_log = [[[0.; 48]; 8]; 16];
+    let turns = numbered(field(round, "card_log_dict")?, 16).map_err(|err| format!("card_log_dict: {err}"))?;
+    if turns.len() != 16 {
+        return Err(format!("{} turns in card_log_dict instead of 16", turns.len()));
+    }
+    for (turn, rows) in turns.into_iter().enumerate() {
+        let rows = items(rows, ROW_KEYS.map(String::from))
+            .map_err(|err| format!("turn {} of card_log_dict: {err}", turn + 1))?;
+        if rows.len() != 8 {
+            return Err(format!("{} rows at turn {} of card_log_dict instead of 8", rows.len(), turn + 1));
+        }
+        for (row, values) in rows.into_iter().enumerate() {
+            let values = values.as_array().ok_or_else(|| format!("{values} is not a list of numbers"))?;
+            if values.len() != 48 {
+                return Err(format!("{} values in a row of card_log_dict instead of 48", values.len()));
+            }
+            for (card, value) in values.iter().enumerate() {
+                card_log[turn][row][card] = number(value)? as f32;
+            }
+        }
+    }
+    let mut koikoi = [[0; 8]; 2];
+    for (player, calls) in numbered(field(round, "koikoi")?, 2).map_err(|err| format!("koikoi: {err}"))?.into_iter().take(2).enumerate() {
+        let calls = calls.as_array().ok_or_else(|| format!("{calls} is not a list of koi-koi calls"))?;
+        for (turn, call) in calls.iter().take(8).enumerate() {
+            koikoi[player][turn] = number(call)? as i32;
+        }
+    }
+    let winner = match round.get("winner") {
+        None | Some(Value::Null) => None,
+        Some(winner) => Some(player(winner)?),
+    };
+    Ok(RoundLog {
+        dealer: player(field(basic, "dealer")?)?,
+        hands: [cards(field(basic, "initHand1")?)?, cards(field(basic, "initHand2")?)?],
+        board: cards(field(basic, "initBoard")?)?,
+        card_log,
+        koikoi,
+        winner,
+    })
+}
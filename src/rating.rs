@@ -0,0 +1,147 @@
+use std::{fmt, fs, io, path::Path};
+use serde::{Deserialize, Serialize};
+
+use crate::arena::ArenaReport;
+use crate::rules::RuleSet;
+
+pub const DEFAULT_RATING: f64 = 1500.;
+/// Elo points per match, small as a pair plays many matches before its ratings are updated.
+pub const DEFAULT_K_FACTOR: f64 = 1.;
+
+/// An agent of a [`RatingPool`], given by its [`AgentSpec`](crate::arena::AgentSpec).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub agent: String,
+    pub rating: f64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Entry {
+    pub fn matches(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Elo ratings of a pool of agents, saved as JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RatingPool {
+    pub k_factor: f64,
+    /// Rules of the matches rated, the KoiKoi-AI rules for the pools saved without them.
+    #[serde(default)]
+    pub rules: RuleSet,
+    pub entries: Vec<Entry>,
+}
+
+impl Default for RatingPool {
+    fn default() -> Self {
+        Self { k_factor: DEFAULT_K_FACTOR, rules: RuleSet::default(), entries: vec!() }
+    }
+}
+
+/// Expected score of a player rated `rating` against one rated `opponent`.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1. / (1. + 10f64.powf((opponent - rating) / 400.))
+}
+
+impl RatingPool {
+    /// Reads the pool saved at `path`, or an empty pool if there is no such file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(io::Error::from),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    /// Adds an agent with the default rating, returns `false` if the name is taken.
+    pub fn add(&mut self, name: &str, agent: &str) -> bool {
+        if self.position(name).is_some() {
+            return false;
+        }
+        self.entries.push(Entry {
+            name: name.to_string(),
+            agent: agent.to_string(),
+            rating: DEFAULT_RATING,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Entry> {
+        self.position(name).map(|i| self.entries.remove(i))
+    }
+
+    /// Rates the next matches under `rules`, returns `false` if other rules were used for
+    /// the matches already recorded.
+    pub fn set_rules(&mut self, rules: RuleSet) -> bool {
+        if rules != self.rules && self.entries.iter().any(|entry| entry.matches() > 0) {
+            return false;
+        }
+        self.rules = rules;
+        true
+    }
+
+    /// Every pair of entries, each one plays once against each other.
+    pub fn round_robin(&self) -> Vec<(usize, usize)> {
+        let n = self.entries.len();
+        (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect()
+    }
+
+    /// Updates the ratings with the matches of `first` against `second`, all rated from the
+    /// ratings before them: a rating moves by `k_factor` times the number of matches times the
+    /// difference between the mean and the expected scores.
+    pub fn record(&mut self, first: usize, second: usize, report: &ArenaReport) {
+        if report.matches() == 0 {
+            return;
+        }
+        let expected = expected_score(self.entries[first].rating, self.entries[second].rating);
+        let score = (report.wins as f64 + 0.5 * report.draws as f64) / report.matches() as f64;
+        let delta = self.k_factor * report.matches() as f64 * (score - expected);
+        self.entries[first].rating += delta;
+        self.entries[second].rating -= delta;
+
+        let entry = &mut self.entries[first];
+        entry.wins += report.wins;
+        entry.draws += report.draws;
+        entry.losses += report.losses;
+        let entry = &mut self.entries[second];
+        entry.wins += report.losses;
+        entry.draws += report.draws;
+        entry.losses += report.wins;
+    }
+
+    /// Entries from the best rated to the worst.
+    pub fn leaderboard(&self) -> Vec<&Entry> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        entries
+    }
+}
+
+impl fmt::Display for RatingPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>4}  {:<20} {:>7} {:>7} {:>18}  agent", "rank", "name", "rating", "matches", "wins/draws/losses")?;
+        for (rank, entry) in self.leaderboard().into_iter().enumerate() {
+            let record = format!("{}/{}/{}", entry.wins, entry.draws, entry.losses);
+            writeln!(
+                f,
+                "{:>4}  {:<20} {:>7.1} {:>7} {:>18}  {}",
+                rank + 1, entry.name, entry.rating, entry.matches(), record, entry.agent,
+            )?;
+        }
+        Ok(())
+    }
+}
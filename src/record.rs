@@ -0,0 +1,161 @@
+use std::{fmt, fs, io::{self, BufRead, BufWriter, Write}, path::Path, time::{SystemTime, UNIX_EPOCH}};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Action, Deal, GameConfig, GameError, GameState, Player};
+use crate::rules::RuleSet;
+
+/// An action of a recorded match.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct RecordedAction {
+    pub player: Player,
+    pub action: Action,
+    /// Milliseconds since the Unix epoch.
+    pub time: u64,
+}
+
+/// A match given by its settings and its actions, the deals are replayed from the seed
+/// unless they are recorded.
+/// Record files hold one match per line as JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub dealer: Player,
+    /// The rules are written next to the other settings, records without them use the defaults.
+    #[serde(flatten)]
+    pub rules: RuleSet,
+    pub init_point: i32,
+    pub player_name: [String; 2],
+    pub actions: Vec<RecordedAction>,
+    /// The cards of each round in place of the ones dealt from the seed, as for imported logs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deals: Vec<Deal>,
+}
+
+/// Why a record does not replay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayError {
+    /// The action `step` is rejected by the engine.
+    Rejected { step: usize, error: GameError },
+    /// The action `step` is recorded for another player than the turn player.
+    WrongPlayer { step: usize, expected: Player, actual: Player },
+    /// The record has deals but none for this round.
+    MissingDeal { round: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Rejected { step, error } => write!(f, "action {step}: {error}"),
+            ReplayError::WrongPlayer { step, expected, actual } =>
+                write!(f, "action {step}: played by the {actual} instead of the {expected}"),
+            ReplayError::MissingDeal { round } => write!(f, "no deal for round {round}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis() as u64)
+}
+
+impl GameRecord {
+    pub fn new(config: &GameConfig) -> Self {
+        Self {
+            seed: config.seed,
+            dealer: config.init_dealer,
+            rules: config.rules,
+            init_point: config.init_point,
+            player_name: config.player_name.clone(),
+            actions: vec!(),
+            deals: vec!(),
+        }
+    }
+
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            rules: self.rules,
+            init_point: self.init_point,
+            init_dealer: self.dealer,
+            player_name: self.player_name.clone(),
+            seed: self.seed,
+        }
+    }
+
+    /// Records `action` of the turn player of `game`, before it is played.
+    pub fn push(&mut self, game: &GameState, action: Action) {
+        self.actions.push(RecordedAction {
+            player: game.round_state.turn_player(),
+            action,
+            time: timestamp(),
+        });
+    }
+
+    /// The game before the action `step`.
+    pub fn state_at(&self, step: usize) -> Result<GameState, ReplayError> {
+        self.replay_until(step, |_, _| {})
+    }
+
+    /// The game after all the actions, `visit` is called with the game before each of them.
+    pub fn replay_with(&self, visit: impl FnMut(usize, &GameState)) -> Result<GameState, ReplayError> {
+        self.replay_until(self.actions.len(), visit)
+    }
+
+    fn replay_until(&self, step: usize, mut visit: impl FnMut(usize, &GameState)) -> Result<GameState, ReplayError> {
+        let mut game = GameState::new(self.config());
+        let mut dealt_round = 0;
+        for (step, recorded) in self.actions[..step].iter().enumerate() {
+            if !self.deals.is_empty() && game.round != dealt_round {
+                self.redeal(&mut game)?;
+                dealt_round = game.round;
+            }
+            visit(step, &game);
+            let expected = game.round_state.turn_player();
+            if recorded.player != expected {
+                return Err(ReplayError::WrongPlayer { step, expected, actual: recorded.player });
+            }
+            game.step(recorded.action).map_err(|error| ReplayError::Rejected { step, error })?;
+        }
+        if !self.deals.is_empty() && game.round != dealt_round && !game.game_over {
+            self.redeal(&mut game)?;
+        }
+        Ok(game)
+    }
+
+    fn redeal(&self, game: &mut GameState) -> Result<(), ReplayError> {
+        let deal = self.deals.get(game.round - 1).ok_or(ReplayError::MissingDeal { round: game.round })?;
+        game.redeal(deal);
+        Ok(())
+    }
+
+    /// The game after all the actions.
+    pub fn replay(&self) -> Result<GameState, ReplayError> {
+        self.state_at(self.actions.len())
+    }
+}
+
+/// Reads a record file, one JSON record per line.
+pub fn read_records(path: &Path) -> io::Result<Vec<GameRecord>> {
+    let mut records = vec!();
+    for (i, line) in io::BufReader::new(fs::File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {err}", i + 1)))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Writes `records` at the end of the record file at `path`.
+pub fn append_records(path: &Path, records: &[GameRecord]) -> io::Result<()> {
+    let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let mut writer = BufWriter::new(file);
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,313 @@
+use std::path::Path;
+use burn::data::dataloader::batcher::Batcher;
+use burn::module::AutodiffModule;
+use burn::optim::{adaptor::OptimizerAdaptor, Adam, AdamConfig, GradientsParams, Optimizer};
+use burn::prelude::*;
+use burn::tensor::activation::log_softmax;
+use burn::tensor::backend::AutodiffBackend;
+
+use crate::agent::{Agent, Decision, NeuralAgent, Sampling};
+use crate::arena::{Arena, ArenaReport};
+use crate::game::{GameConfig, Player};
+use crate::game_tensor::feature_array;
+use crate::model::{DiscardModel, KoiKoiModel, KoiKoiNetConfig, LoadError, MpkRecorder, PickModel};
+use crate::record::{GameRecord, ReplayError};
+use crate::rules::RuleSet;
+use crate::train::{DecisionBatch, DecisionBatcher, DecisionItem};
+
+const DECISIONS: [Decision; 3] = [Decision::Discard, Decision::Pick, Decision::KoiKoi];
+
+/// A decision of a match with the reward of the player who took it.
+#[derive(Clone, Debug)]
+pub struct PolicySample {
+    pub decision: Decision,
+    pub player: Player,
+    pub item: DecisionItem,
+    /// Points won by the player in the round of the decision, plus the discounted points
+    /// of the next rounds.
+    pub reward: f32,
+}
+
+/// The decisions of a recorded match, rewarded with the point deltas of [`crate::game::GameState::points`].
+/// The points of a round count `discount` times less at each round before it.
+pub fn decision_samples(record: &GameRecord, discount: f32) -> Result<Vec<PolicySample>, ReplayError> {
+    let mut samples = vec!();
+    let mut sample_rounds = vec!();
+    // points at the start of each round
+    let mut round_points = vec!();
+    let game = record.replay_with(|step, game| {
+        if round_points.len() < game.round {
+            round_points.push(game.points);
+        }
+        let Some(decision) = Decision::of(&game.round_state) else {
+            return;
+        };
+        let mut legal = vec![false; decision.output_len()];
+        for action in game.legal_actions() {
+            legal[Decision::index(action).unwrap()] = true;
+        }
+        let recorded = record.actions[step];
+        samples.push(PolicySample {
+            decision,
+            player: recorded.player,
+            item: DecisionItem {
+                features: feature_array(&game.observation(recorded.player)).iter().copied().collect(),
+                target: Decision::index(recorded.action).unwrap(),
+                legal,
+            },
+            reward: 0.,
+        });
+        sample_rounds.push(game.round - 1);
+    })?;
+    round_points.push(game.points);
+
+    let mut rewards = vec![[0.; 2]; round_points.len() - 1];
+    for round in (0..rewards.len()).rev() {
+        for player in Player::BOTH {
+            let next = rewards.get(round + 1).map_or(0., |next: &[f32; 2]| next[player]);
+            let delta = round_points[round + 1][player] - round_points[round][player];
+            rewards[round][player] = delta as f32 + discount * next;
+        }
+    }
+    for (sample, round) in samples.iter_mut().zip(sample_rounds) {
+        sample.reward = rewards[round][sample.player];
+    }
+    Ok(samples)
+}
+
+/// REINFORCE loss of a batch: the log-probabilities of the actions taken weighted by their
+/// advantages, minus the entropy of the policies times `entropy_coef`.
+/// Returns the loss and the mean entropy.
+pub fn policy_gradient_loss<B: Backend>(
+    logits: Tensor<B, 2>,
+    batch: DecisionBatch<B>,
+    advantages: Tensor<B, 1>,
+    entropy_coef: f32,
+) -> (Tensor<B, 1>, Tensor<B, 1>) {
+    let log_probs = log_softmax(logits.mask_fill(batch.legal.bool_not(), -1e9), 1);
+    let taken = log_probs.clone().gather(1, batch.targets.unsqueeze_dim(1)).squeeze(1);
+    let entropy = (log_probs.clone().exp() * log_probs).sum_dim(1).mean().neg();
+    let loss = (taken * advantages).mean().neg() - entropy.clone() * entropy_coef;
+    (loss, entropy)
+}
+
+/// Hyperparameters of the self-play training.
+#[derive(Config)]
+pub struct SelfPlayConfig {
+    pub optimizer: AdamConfig,
+    /// Matches played at each iteration.
+    #[config(default = 8)]
+    pub matches: usize,
+    /// Rules of the matches played and evaluated.
+    #[config(default = "RuleSet::default()")]
+    pub rules: RuleSet,
+    /// Temperature of the sampling of the actions in the matches.
+    #[config(default = 1.0)]
+    pub temperature: f32,
+    /// Weight of the points of the next round in the reward of a decision,
+    /// 0 rewards the points of its round only.
+    #[config(default = 0.0)]
+    pub discount: f32,
+    #[config(default = 0.01)]
+    pub entropy_coef: f32,
+    #[config(default = 32)]
+    pub batch_size: usize,
+    #[config(default = 1.0e-5)]
+    pub learning_rate: f64,
+    #[config(default = 0)]
+    pub seed: u64,
+}
+
+/// Statistics of an iteration, one row of the CSV learning curves.
+#[derive(Clone, Debug, Default)]
+pub struct IterationLog {
+    pub iteration: usize,
+    /// Number of decisions of each network, in the order discard, pick, koi-koi.
+    pub positions: [usize; 3],
+    pub loss: [f32; 3],
+    pub entropy: [f32; 3],
+    /// Matches against the reference agent, when evaluated.
+    pub evaluation: Option<ArenaReport>,
+}
+
+impl IterationLog {
+    pub const CSV_HEADER: &'static str = "iteration,discard_positions,pick_positions,koikoi_positions,\
+        discard_loss,pick_loss,koikoi_loss,discard_entropy,pick_entropy,koikoi_entropy,win_rate,point_diff";
+
+    /// The statistics in the order of [`IterationLog::CSV_HEADER`], the evaluation is empty if there is none.
+    pub fn csv_row(&self) -> String {
+        let mut fields = vec![self.iteration.to_string()];
+        fields.extend(self.positions.iter().map(usize::to_string));
+        fields.extend(self.loss.iter().chain(&self.entropy).map(f32::to_string));
+        match &self.evaluation {
+            Some(report) => {
+                fields.push(report.win_rate().mean.to_string());
+                fields.push(report.point_diff().mean.to_string());
+            }
+            None => fields.extend([String::new(), String::new()]),
+        }
+        fields.join(",")
+    }
+}
+
+type Adaptor<B, M> = OptimizerAdaptor<Adam<<B as AutodiffBackend>::InnerBackend>, M, B>;
+
+/// Fine-tunes the three networks with REINFORCE on matches they play against themselves.
+pub struct SelfPlay<B: AutodiffBackend> {
+    net: KoiKoiNetConfig,
+    config: SelfPlayConfig,
+    discard_model: DiscardModel<B>,
+    pick_model: PickModel<B>,
+    koikoi_model: KoiKoiModel<B>,
+    discard_optim: Adaptor<B, DiscardModel<B>>,
+    pick_optim: Adaptor<B, PickModel<B>>,
+    koikoi_optim: Adaptor<B, KoiKoiModel<B>>,
+    iteration: usize,
+    device: B::Device,
+}
+
+impl<B: AutodiffBackend> SelfPlay<B> {
+    /// Starts from the networks of `agent`, of hyperparameters `net`.
+    pub fn new(agent: NeuralAgent<B>, net: KoiKoiNetConfig, config: SelfPlayConfig, device: B::Device) -> Self {
+        let (discard_model, pick_model, koikoi_model) = agent.into_models();
+        Self {
+            discard_optim: config.optimizer.init(),
+            pick_optim: config.optimizer.init(),
+            koikoi_optim: config.optimizer.init(),
+            net,
+            config,
+            discard_model,
+            pick_model,
+            koikoi_model,
+            iteration: 0,
+            device,
+        }
+    }
+
+    /// Number of iterations done.
+    pub fn iterations(&self) -> usize {
+        self.iteration
+    }
+
+    /// The current networks as an agent.
+    pub fn agent(&self, sampling: Sampling) -> NeuralAgent<B::InnerBackend> {
+        NeuralAgent::new(
+            self.discard_model.valid(),
+            self.pick_model.valid(),
+            self.koikoi_model.valid(),
+            sampling,
+            self.device.clone(),
+        )
+    }
+
+    /// Plays the matches of an iteration and takes a gradient step per batch of their decisions.
+    /// The rewards are normalized over all the decisions of the iteration.
+    pub fn iterate(&mut self) -> IterationLog {
+        let seed = self.config.seed + (self.iteration * self.config.matches) as u64;
+        let game_config = self.game_config(seed);
+        let sampling = Sampling::Temperature(self.config.temperature);
+        let mut first = self.agent(sampling).with_seed(2 * seed);
+        let mut second = self.agent(sampling).with_seed(2 * seed + 1);
+        let (_, records) = Arena::new(self.config.matches, game_config).play_recorded(&mut first, &mut second);
+        let samples: Vec<_> = records
+            .iter()
+            .flat_map(|record| decision_samples(record, self.config.discount).expect("self-play matches replay"))
+            .collect();
+
+        let n = samples.len().max(1) as f32;
+        let mean = samples.iter().map(|sample| sample.reward).sum::<f32>() / n;
+        let std = (samples.iter().map(|sample| (sample.reward - mean).powi(2)).sum::<f32>() / n).sqrt();
+        let advantage = |sample: &PolicySample| (sample.reward - mean) / std.max(1e-6);
+
+        let mut log = IterationLog { iteration: self.iteration + 1, ..IterationLog::default() };
+        for (i, decision) in DECISIONS.into_iter().enumerate() {
+            let samples: Vec<_> = samples
+                .iter()
+                .filter(|sample| sample.decision == decision)
+                .map(|sample| (sample, advantage(sample)))
+                .collect();
+            log.positions[i] = samples.len();
+            (log.loss[i], log.entropy[i]) = match decision {
+                Decision::Discard => {
+                    let optim = &mut self.discard_optim;
+                    let model = self.discard_model.clone();
+                    let (model, loss, entropy) = update(model, optim, DiscardModel::forward, &samples, &self.config, &self.device);
+                    self.discard_model = model;
+                    (loss, entropy)
+                }
+                Decision::Pick => {
+                    let optim = &mut self.pick_optim;
+                    let model = self.pick_model.clone();
+                    let (model, loss, entropy) = update(model, optim, PickModel::forward, &samples, &self.config, &self.device);
+                    self.pick_model = model;
+                    (loss, entropy)
+                }
+                Decision::KoiKoi => {
+                    let optim = &mut self.koikoi_optim;
+                    let model = self.koikoi_model.clone();
+                    let (model, loss, entropy) = update(model, optim, KoiKoiModel::forward, &samples, &self.config, &self.device);
+                    self.koikoi_model = model;
+                    (loss, entropy)
+                }
+            };
+        }
+        self.iteration += 1;
+        log
+    }
+
+    /// Plays the current networks, taking their best actions, against `reference`.
+    /// The deals are the same at each evaluation.
+    pub fn evaluate(&self, reference: &mut dyn Agent, matches: usize) -> ArenaReport {
+        let config = self.game_config(self.config.seed);
+        Arena::new(matches, config).play(&mut self.agent(Sampling::Argmax), reference)
+    }
+
+    fn game_config(&self, seed: u64) -> GameConfig {
+        GameConfig { rules: self.config.rules, seed, ..GameConfig::default() }
+    }
+
+    /// Saves the networks in `out_dir` as `{discard,pick,koikoi}_{tag}.mpk`, next to their config.
+    pub fn save(&self, out_dir: &Path, tag: &str) -> Result<(), LoadError> {
+        let path = |decision: Decision| out_dir.join(format!("{}_{tag}.mpk", decision.name()));
+        self.discard_model.clone().save_file(path(Decision::Discard), &MpkRecorder::new())?;
+        self.pick_model.clone().save_file(path(Decision::Pick), &MpkRecorder::new())?;
+        self.koikoi_model.clone().save_file(path(Decision::KoiKoi), &MpkRecorder::new())?;
+        for decision in DECISIONS {
+            self.net
+                .save(KoiKoiNetConfig::checkpoint_path(&path(decision)))
+                .map_err(|err| LoadError::Config(err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// One pass of gradient steps on the samples of a network, with their advantages.
+/// Returns the updated network with its mean loss and entropy.
+fn update<B, M>(
+    mut model: M,
+    optim: &mut impl Optimizer<M, B>,
+    forward: impl Fn(&M, Tensor<B, 3>) -> Tensor<B, 2>,
+    samples: &[(&PolicySample, f32)],
+    config: &SelfPlayConfig,
+    device: &B::Device,
+) -> (M, f32, f32)
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let batcher = DecisionBatcher::<B>::new(device.clone());
+    let (mut loss_sum, mut entropy_sum) = (0., 0.);
+    for chunk in samples.chunks(config.batch_size) {
+        let batch = batcher.batch(chunk.iter().map(|(sample, _)| sample.item.clone()).collect());
+        let advantages = chunk.iter().map(|&(_, advantage)| advantage).collect();
+        let advantages = Tensor::from_data(TensorData::new(advantages, [chunk.len()]), device);
+        let logits = forward(&model, batch.features.clone());
+        let (loss, entropy) = policy_gradient_loss(logits, batch, advantages, config.entropy_coef);
+        loss_sum += loss.clone().into_scalar().elem::<f32>() * chunk.len() as f32;
+        entropy_sum += entropy.into_scalar().elem::<f32>() * chunk.len() as f32;
+        let grads = GradientsParams::from_grads(loss.backward(), &model);
+        model = optim.step(config.learning_rate, model, grads);
+    }
+    let n = samples.len().max(1) as f32;
+    (model, loss_sum / n, entropy_sum / n)
+}
@@ -0,0 +1,105 @@
+//! House rules of a match. A rule set is read from a TOML file, or from a JSON file for
+//! any other extension, the missing fields taking their default values:
+//!
+//! ```toml
+//! round_total = 12
+//! viewing_points = [5, 5]
+//! double_at_seven = true
+//! koikoi_bonus = "doubling"
+//! ```
+
+use std::{fmt, fs, io, path::Path};
+use serde::{Deserialize, Serialize};
+
+/// How the koi-koi calls change the points of the yaku.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KoiKoiBonus {
+    /// Each call of the winner adds one point up to three, beyond that the total is multiplied
+    /// by the number of calls minus two.
+    #[default]
+    Additive,
+    /// The points are doubled when the opponent of the winner called koi-koi.
+    Doubling,
+    /// The calls do not change the points.
+    None,
+}
+
+/// The rules of the KoiKoi-AI environment by default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Rounds of a match.
+    pub round_total: usize,
+    /// Whether the flower and moon viewing sake count as yaku.
+    pub viewing_yaku: bool,
+    /// Points of a viewing yaku before and after the first koi-koi call of the player.
+    pub viewing_points: [i32; 2],
+    /// Whether the sake cup also counts as a chaff.
+    pub sake_cup_chaff: bool,
+    /// Whether the yaku worth 7 points or more are doubled, before the koi-koi bonus.
+    pub double_at_seven: bool,
+    pub koikoi_bonus: KoiKoiBonus,
+    /// Whether a hand dealt with four cards of a month or four pairs wins the round at once,
+    /// the hands with four cards of a month are redealt otherwise.
+    pub instant_wins: bool,
+    /// Points of an instant win.
+    pub instant_win_points: i32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            round_total: 8,
+            viewing_yaku: true,
+            viewing_points: [1, 3],
+            sake_cup_chaff: true,
+            double_at_seven: false,
+            koikoi_bonus: KoiKoiBonus::Additive,
+            instant_wins: false,
+            instant_win_points: 6,
+        }
+    }
+}
+
+impl RuleSet {
+    /// The rules of the Nintendo hanafuda games: a round per month, viewing yaku worth 5,
+    /// yaku of 7 points or more doubled, the points doubled by the koi-koi of the opponent and
+    /// the instant wins at the deal.
+    pub fn nintendo() -> Self {
+        Self {
+            round_total: 12,
+            viewing_points: [5, 5],
+            double_at_seven: true,
+            koikoi_bonus: KoiKoiBonus::Doubling,
+            instant_wins: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RulesError> {
+        let text = fs::read_to_string(path).map_err(RulesError::Read)?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&text).map_err(|err| RulesError::Parse(err.to_string()))
+        } else {
+            serde_json::from_str(&text).map_err(|err| RulesError::Parse(err.to_string()))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    Read(io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::Read(err) => write!(f, "failed to read the rules: {err}"),
+            RulesError::Parse(err) => write!(f, "invalid rules: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use burn::data::dataloader::{batcher::Batcher, DataLoaderBuilder};
+use burn::data::dataset::Dataset;
+use burn::module::AutodiffModule;
+use burn::nn::loss::CrossEntropyLossConfig;
+use burn::optim::AdamConfig;
+use burn::prelude::*;
+use burn::record::CompactRecorder;
+use burn::tensor::backend::AutodiffBackend;
+use burn::train::metric::{AccuracyMetric, LossMetric};
+use burn::train::renderer::{MetricState, MetricsRenderer, TrainingProgress};
+use burn::train::{ClassificationOutput, LearnerBuilder, TrainOutput, TrainStep, ValidStep};
+
+use crate::agent::Decision;
+use crate::game::{Action, GameState};
+use crate::game_tensor::{feature_array, FEATURE_ROWS};
+use crate::model::{DiscardModel, KoiKoiModel, KoiKoiNetConfig, LoadError, MpkRecorder, PickModel};
+use crate::record::{GameRecord, ReplayError};
+
+/// A position where a decision was taken, with the action chosen.
+#[derive(Clone, Debug)]
+pub struct DecisionItem {
+    /// The `300 x 48` features of the position.
+    pub features: Vec<f32>,
+    /// Logit index of the action, see [`Decision::index`].
+    pub target: usize,
+    /// Which logits are legal actions.
+    pub legal: Vec<bool>,
+}
+
+/// The positions of the recorded matches where a given decision was taken, each record
+/// being replayed once when the dataset is built.
+pub struct DecisionDataset {
+    decision: Decision,
+    items: Vec<DecisionItem>,
+}
+
+impl DecisionDataset {
+    /// Fails if one of the records does not replay.
+    pub fn new(records: &[GameRecord], decision: Decision) -> Result<Self, ReplayError> {
+        let mut items = vec!();
+        for record in records {
+            record.replay_with(|step, game| {
+                if Decision::of(&game.round_state) == Some(decision) {
+                    items.extend(decision_item(game, decision, record.actions[step].action));
+                }
+            })?;
+        }
+        Ok(Self { decision, items })
+    }
+
+    pub fn decision(&self) -> Decision {
+        self.decision
+    }
+}
+
+fn decision_item(game: &GameState, decision: Decision, action: Action) -> Option<DecisionItem> {
+    let mut legal = vec![false; decision.output_len()];
+    for action in game.legal_actions() {
+        legal[Decision::index(action)?] = true;
+    }
+    Some(DecisionItem {
+        features: feature_array(&game.observation(game.round_state.turn_player())).iter().copied().collect(),
+        target: Decision::index(action)?,
+        legal,
+    })
+}
+
+impl Dataset<DecisionItem> for DecisionDataset {
+    fn get(&self, index: usize) -> Option<DecisionItem> {
+        self.items.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DecisionBatch<B: Backend> {
+    pub features: Tensor<B, 3>,
+    pub targets: Tensor<B, 1, Int>,
+    pub legal: Tensor<B, 2, Bool>,
+}
+
+#[derive(Clone)]
+pub struct DecisionBatcher<B: Backend> {
+    device: B::Device,
+}
+
+impl<B: Backend> DecisionBatcher<B> {
+    pub fn new(device: B::Device) -> Self {
+        Self { device }
+    }
+}
+
+impl<B: Backend> Batcher<DecisionItem, DecisionBatch<B>> for DecisionBatcher<B> {
+    fn batch(&self, items: Vec<DecisionItem>) -> DecisionBatch<B> {
+        let n = items.len();
+        let outputs = items.first().map_or(0, |item| item.legal.len());
+        let features: Vec<_> = items.iter().flat_map(|item| item.features.iter().copied()).collect();
+        let targets: Vec<_> = items.iter().map(|item| item.target as i64).collect();
+        let legal: Vec<_> = items.iter().flat_map(|item| item.legal.iter().copied()).collect();
+        DecisionBatch {
+            features: Tensor::from_data(TensorData::new(features, [n, FEATURE_ROWS, 48]), &self.device),
+            targets: Tensor::from_data(TensorData::new(targets, [n]), &self.device),
+            legal: Tensor::from_data(TensorData::new(legal, [n, outputs]), &self.device),
+        }
+    }
+}
+
+/// Cross-entropy of the logits restricted to the legal actions.
+pub fn masked_classification<B: Backend>(logits: Tensor<B, 2>, batch: DecisionBatch<B>) -> ClassificationOutput<B> {
+    let logits = logits.mask_fill(batch.legal.bool_not(), -1e9);
+    let loss = CrossEntropyLossConfig::new()
+        .init(&logits.device())
+        .forward(logits.clone(), batch.targets.clone());
+    ClassificationOutput::new(loss, logits, batch.targets)
+}
+
+macro_rules! decision_steps {
+    ($($model:ident),*) => {$(
+        impl<B: AutodiffBackend> TrainStep<DecisionBatch<B>, ClassificationOutput<B>> for $model<B> {
+            fn step(&self, batch: DecisionBatch<B>) -> TrainOutput<ClassificationOutput<B>> {
+                let item = masked_classification(self.forward(batch.features.clone()), batch);
+                TrainOutput::new(self, item.loss.backward(), item)
+            }
+        }
+
+        impl<B: Backend> ValidStep<DecisionBatch<B>, ClassificationOutput<B>> for $model<B> {
+            fn step(&self, batch: DecisionBatch<B>) -> ClassificationOutput<B> {
+                masked_classification(self.forward(batch.features.clone()), batch)
+            }
+        }
+    )*};
+}
+
+decision_steps!(DiscardModel, PickModel, KoiKoiModel);
+
+/// Hyperparameters of the supervised training.
+#[derive(Config)]
+pub struct TrainingConfig {
+    pub net: KoiKoiNetConfig,
+    pub optimizer: AdamConfig,
+    #[config(default = 10)]
+    pub num_epochs: usize,
+    #[config(default = 64)]
+    pub batch_size: usize,
+    #[config(default = 4)]
+    pub num_workers: usize,
+    #[config(default = 42)]
+    pub seed: u64,
+    #[config(default = 1.0e-4)]
+    pub learning_rate: f64,
+}
+
+/// Prints the metrics at the end of each epoch, in place of the terminal dashboard of burn.
+#[derive(Default)]
+struct EpochRenderer {
+    train: Vec<String>,
+    valid: Vec<String>,
+}
+
+fn update_metric(metrics: &mut Vec<String>, state: MetricState) {
+    let (MetricState::Generic(entry) | MetricState::Numeric(entry, _)) = state;
+    let line = format!("{}: {}", entry.name, entry.formatted);
+    match metrics.iter_mut().find(|metric| metric.starts_with(&format!("{}: ", entry.name))) {
+        Some(metric) => *metric = line,
+        None => metrics.push(line),
+    }
+}
+
+fn render_epoch(split: &str, metrics: &[String], item: &TrainingProgress) {
+    if item.progress.items_processed >= item.progress.items_total {
+        println!("epoch {}/{} {split}: {}", item.epoch, item.epoch_total, metrics.join(", "));
+    }
+}
+
+impl MetricsRenderer for EpochRenderer {
+    fn update_train(&mut self, state: MetricState) {
+        update_metric(&mut self.train, state);
+    }
+
+    fn update_valid(&mut self, state: MetricState) {
+        update_metric(&mut self.valid, state);
+    }
+
+    fn render_train(&mut self, item: TrainingProgress) {
+        render_epoch("train", &self.train, &item);
+    }
+
+    fn render_valid(&mut self, item: TrainingProgress) {
+        render_epoch("valid", &self.valid, &item);
+    }
+}
+
+fn fit<B, M>(
+    model: M,
+    artifact_dir: &Path,
+    config: &TrainingConfig,
+    train: DecisionDataset,
+    valid: DecisionDataset,
+    device: B::Device,
+) -> M
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B> + TrainStep<DecisionBatch<B>, ClassificationOutput<B>> + std::fmt::Display + 'static,
+    M::InnerModule: ValidStep<DecisionBatch<B::InnerBackend>, ClassificationOutput<B::InnerBackend>>,
+{
+    let train_loader = DataLoaderBuilder::new(DecisionBatcher::<B>::new(device.clone()))
+        .batch_size(config.batch_size)
+        .shuffle(config.seed)
+        .num_workers(config.num_workers)
+        .build(train);
+    let valid_loader = DataLoaderBuilder::new(DecisionBatcher::<B::InnerBackend>::new(device.clone()))
+        .batch_size(config.batch_size)
+        .num_workers(config.num_workers)
+        .build(valid);
+
+    let learner = LearnerBuilder::new(artifact_dir)
+        .metric_train_numeric(AccuracyMetric::new())
+        .metric_valid_numeric(AccuracyMetric::new())
+        .metric_train_numeric(LossMetric::new())
+        .metric_valid_numeric(LossMetric::new())
+        .with_file_checkpointer(CompactRecorder::new())
+        .devices(vec![device])
+        .num_epochs(config.num_epochs)
+        .renderer(EpochRenderer::default())
+        .build(model, config.optimizer.init(), config.learning_rate);
+    learner.fit(train_loader, valid_loader)
+}
+
+/// Trains the network taking the decision of the datasets and saves it in `out_dir`
+/// as `{discard,pick,koikoi}_{tag}.mpk`, see [`crate::agent::NeuralAgent::load`].
+/// The checkpoints of each epoch are kept in a subdirectory of the same name.
+pub fn train<B: AutodiffBackend>(
+    out_dir: &Path,
+    tag: &str,
+    config: &TrainingConfig,
+    train: DecisionDataset,
+    valid: DecisionDataset,
+    device: B::Device,
+) -> Result<PathBuf, LoadError> {
+    let decision = train.decision;
+    assert_eq!(decision, valid.decision, "the datasets must be of the same decision");
+    let name = decision.name();
+    let path = out_dir.join(format!("{name}_{tag}.mpk"));
+    let artifact_dir = out_dir.join(format!("{name}_{tag}"));
+    println!("{name}: {} training and {} validation positions", train.len(), valid.len());
+    match decision {
+        Decision::Discard => fit(config.net.init_discard::<B>(&device), &artifact_dir, config, train, valid, device)
+            .save_file(&path, &MpkRecorder::new())?,
+        Decision::Pick => fit(config.net.init_pick::<B>(&device), &artifact_dir, config, train, valid, device)
+            .save_file(&path, &MpkRecorder::new())?,
+        Decision::KoiKoi => fit(config.net.init_koikoi::<B>(&device), &artifact_dir, config, train, valid, device)
+            .save_file(&path, &MpkRecorder::new())?,
+    }
+    config.net
+        .save(KoiKoiNetConfig::checkpoint_path(&path))
+        .map_err(|err| LoadError::Config(err.to_string()))?;
+    Ok(path)
+}
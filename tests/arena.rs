@@ -0,0 +1,26 @@
+use rust_burn_test::arena::{Arena, Estimate, GreedyAgent, RandomAgent};
+use rust_burn_test::game::GameConfig;
+
+#[test]
+fn seats_are_swapped_on_the_same_deals() {
+    let report = Arena::new(40, GameConfig::default()).play(&mut GreedyAgent, &mut GreedyAgent);
+    assert_eq!(report.matches(), 40);
+    assert_eq!(report.wins, report.losses);
+    assert_eq!(report.point_diff().mean, 0.);
+    assert_eq!(report.win_rate().mean, 0.5);
+}
+
+#[test]
+fn greedy_beats_random() {
+    let report = Arena::new(100, GameConfig::default()).play(&mut GreedyAgent, &mut RandomAgent::new(0));
+    assert_eq!(report.wins + report.draws + report.losses, 100);
+    assert!(report.win_rate().interval().0 > 0.5, "{report}");
+}
+
+#[test]
+fn estimate_interval() {
+    let estimate = Estimate::of([1., 0., 1., 0.].into_iter());
+    assert_eq!(estimate.mean, 0.5);
+    let margin = 1.96 * (1. / 3. / 4f64).sqrt();
+    assert!((estimate.margin - margin).abs() < 1e-12);
+}
@@ -0,0 +1,67 @@
+use burn::backend::{ndarray::NdArrayDevice, NdArray};
+use rust_burn_test::agent::{Agent, NeuralAgent, Sampling};
+use rust_burn_test::game::{GameConfig, GameObservation, GameState};
+use rust_burn_test::game_tensor::{feature_batch, feature_tensor};
+use rust_burn_test::model::{DiscardModel, KoiKoiModel, KoiKoiNet, KoiKoiNetConfig, PickModel};
+use rust_burn_test::rules::RuleSet;
+
+fn games() -> Vec<GameObservation> {
+    (0..3)
+        .map(|seed| {
+            let mut game = GameState::new(GameConfig { seed, ..GameConfig::default() });
+            for _ in 0..seed * 3 {
+                let action = game.legal_actions()[0];
+                game.step(action).unwrap();
+            }
+            game.observation(game.round_state.turn_player())
+        })
+        .collect()
+}
+
+#[test]
+fn batch_matches_single_games() {
+    let device = NdArrayDevice::default();
+    let games = games();
+    let refs: Vec<_> = games.iter().collect();
+
+    let batch = feature_batch::<NdArray>(&refs, &device);
+    assert_eq!(batch.dims(), [3, 300, 48]);
+    for (i, game) in games.iter().enumerate() {
+        let single = feature_tensor::<NdArray>(game, &device);
+        assert_eq!(batch.clone().narrow(0, i, 1).into_data(), single.into_data());
+    }
+
+    let agent = NeuralAgent::new(
+        DiscardModel::<NdArray>::new(&device),
+        PickModel::new(&device),
+        KoiKoiModel::new(&device),
+        Sampling::Temperature(1.),
+        device,
+    );
+    for (game, batched) in games.iter().zip(agent.policy_batch(&refs)) {
+        for ((a, p), (b, q)) in agent.policy(game).into_iter().zip(batched) {
+            assert_eq!(a, b);
+            assert!((p - q).abs() < 1e-4, "{a:?}: {p} alone, {q} in a batch");
+        }
+    }
+}
+
+#[test]
+fn networks_play_the_twelve_rounds_of_nintendo_matches() {
+    let device = NdArrayDevice::default();
+    let net = KoiKoiNetConfig::new().with_n_emb(8).with_n_fw(16).with_n_heads(2).with_n_layers(1);
+    let mut agent = NeuralAgent::new(
+        DiscardModel::<NdArray>::init(&net, &device),
+        PickModel::init(&net, &device),
+        KoiKoiModel::init(&net, &device),
+        Sampling::Argmax,
+        device,
+    );
+    // enough points to play every round
+    let mut game = GameState::new(GameConfig { rules: RuleSet::nintendo(), init_point: 1000, ..GameConfig::default() });
+    while !game.game_over {
+        let action = agent.act(&game.observation(game.round_state.turn_player()));
+        game.step(action).unwrap();
+    }
+    assert_eq!(game.round, 12);
+}
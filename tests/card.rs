@@ -0,0 +1,40 @@
+use rust_burn_test::game::{Card, CardKind};
+
+#[test]
+fn cards_are_indexed_by_month_then_rank() {
+    for (index, card) in Card::all().enumerate() {
+        assert_eq!(card.index(), index);
+        assert_eq!(Card::from_index(index), card);
+        assert_eq!(Card::new(card.month(), card.rank()), card);
+    }
+    assert_eq!(Card::new(2, 3).index(), 6);
+    assert!(Card::new(1, 4) < Card::new(2, 1));
+}
+
+#[test]
+fn kinds() {
+    let count = |kind| Card::all().filter(|card| card.counts_as(kind)).count();
+    assert_eq!(
+        [CardKind::Bright, CardKind::Animal, CardKind::Ribbon, CardKind::Chaff].map(count),
+        [5, 9, 10, 25],
+    );
+    let sake_cup = Card::new(9, 1);
+    assert_eq!(sake_cup.kind(), CardKind::Animal);
+    assert!(sake_cup.counts_as(CardKind::Chaff));
+    assert_eq!(Card::new(11, 1).kind(), CardKind::Bright);
+    assert_eq!(Card::new(11, 4).kind(), CardKind::Chaff);
+}
+
+#[test]
+fn names() {
+    assert_eq!(Card::new(8, 1).to_string(), "Full Moon");
+    assert_eq!(format!("{:#}", Card::new(8, 1)), "芒に月");
+    assert_eq!(format!("{:?}", Card::new(8, 1)), "(8, 1)");
+}
+
+#[test]
+fn cards_are_serialized_as_pairs() {
+    assert_eq!(serde_json::to_string(&Card::new(12, 4)).unwrap(), "[12,4]");
+    assert_eq!(serde_json::from_str::<Card>("[3,1]").unwrap(), Card::new(3, 1));
+    assert!(serde_json::from_str::<Card>("[13,1]").is_err());
+}
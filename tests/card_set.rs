@@ -0,0 +1,34 @@
+use rust_burn_test::game::{Card, CardKind, CardSet};
+
+#[test]
+fn set_operations() {
+    let mut set = CardSet::EMPTY;
+    assert!(set.insert(Card::new(3, 1)));
+    assert!(!set.insert(Card::new(3, 1)));
+    set.extend([Card::new(1, 4), Card::new(12, 2)]);
+    assert_eq!(set.len(), 3);
+    assert_eq!(set.iter().collect::<Vec<_>>(), [Card::new(1, 4), Card::new(3, 1), Card::new(12, 2)]);
+    assert!(set.remove(Card::new(1, 4)));
+    assert!(!set.contains(Card::new(1, 4)));
+
+    let march = CardSet::month(3);
+    assert_eq!(march.len(), 4);
+    assert_eq!(CardSet::month(12).iter().collect::<Vec<_>>(), (1..=4).map(|rank| Card::new(12, rank)).collect::<Vec<_>>());
+    assert_eq!(CardSet::month(0), CardSet::EMPTY);
+    assert_eq!(CardSet::month(13), CardSet::EMPTY);
+    assert_eq!(set & march, CardSet::from(Card::new(3, 1)));
+    assert_eq!((set | march).len(), 5);
+    assert_eq!(set - march, CardSet::from(Card::new(12, 2)));
+    assert_eq!((!set).len(), 46);
+    assert!(CardSet::from(Card::new(3, 1)).is_subset(set));
+    assert_eq!(CardSet::ALL.len(), 48);
+    assert_eq!(CardSet::of_kind(CardKind::Chaff).len(), 25);
+}
+
+#[test]
+fn sets_are_serialized_as_lists() {
+    let set: CardSet = [Card::new(2, 2), Card::new(1, 1)].into_iter().collect();
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json, "[[1,1],[2,2]]");
+    assert_eq!(serde_json::from_str::<CardSet>(&json).unwrap(), set);
+}
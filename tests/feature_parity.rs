@@ -0,0 +1,199 @@
+//! Compares `feature_tensor` with the features computed by the Python KoiKoi-AI
+//! environment. Fixtures are written by `dump_features.py` in `tests/fixtures/features`,
+//! one JSON file per position:
+//!
+//! ```json
+//! {
+//!   "koikoi_ai_commit": "<commit of the KoiKoi-AI checkout>",
+//!   "round": 1,
+//!   "points": [30, 30],
+//!   "round_state": {
+//!     "dealer": 0, "turn_16": 3, "state": "discard-pick",
+//!     "hand": [[[1, 1], ...], [...]], "pile": [[...], [...]],
+//!     "field": [...], "stock": [...], "init_board": [...], "show": [[4, 2]],
+//!     "koikoi": [[0, 0, 0, 0, 0, 0, 0, 0], [0, 0, 0, 0, 0, 0, 0, 0]],
+//!     "card_log": [16 x 8 x 48 floats]
+//!   },
+//!   "features": [300 x 48 floats]
+//! }
+//! ```
+//!
+//! Players and dealers are 0 and 1, cards are `[month, rank]` pairs.
+//!
+//! The fixtures are not in the tree yet, so the comparison is ignored by default: write them,
+//! then run `cargo test --test feature_parity -- --ignored`, and drop the `#[ignore]` once
+//! they are committed. Until then, no Python output checks these parts of the layout: the
+//! moon of `CARD_LIST` in the yaku status block, the `feature_tuple` of negative values in
+//! the game status block, the init board row of the init position block and the order of
+//! the 8 rows of each turn in the log block. The mismatches are reported with the block and
+//! the row they fall in.
+
+use std::{fs, path::Path};
+
+use burn::backend::{ndarray::NdArrayDevice, NdArray};
+use rust_burn_test::game::{Card, CardSet, GameConfig, GameState, Player, State};
+use rust_burn_test::game_tensor::feature_tensor;
+use serde::Deserialize;
+
+const FIXTURE_DIR: &str = "tests/fixtures/features";
+const TOLERANCE: f32 = 1e-5;
+
+const BLOCKS: [(&str, usize); 8] = [
+    ("reserve", 17),
+    ("game status", 55),
+    ("yaku status", 78),
+    ("suit", 12),
+    ("init position", 3),
+    ("current position", 5),
+    ("pairing state", 2),
+    ("log", 128),
+];
+
+#[derive(Deserialize)]
+struct Fixture {
+    round: usize,
+    points: [i32; 2],
+    round_state: FixtureRound,
+    features: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct FixtureRound {
+    dealer: Player,
+    turn_16: usize,
+    state: String,
+    hand: [CardSet; 2],
+    pile: [CardSet; 2],
+    field: CardSet,
+    stock: Vec<Card>,
+    init_board: CardSet,
+    show: Vec<Card>,
+    koikoi: [[i32; 8]; 2],
+    card_log: Vec<Vec<Vec<f32>>>,
+}
+
+fn parse_state(state: &str) -> State {
+    match state {
+        "init" => State::Init,
+        "discard" => State::Discard,
+        "discard-pick" => State::DiscardPick,
+        "draw" => State::Draw,
+        "draw-pick" => State::DrawPick,
+        "koikoi" => State::KoiKoi,
+        "round-over" => State::RoundOver,
+        _ => panic!("unknown state {state}"),
+    }
+}
+
+fn game_from_fixture(fixture: &Fixture) -> GameState {
+    let mut game = GameState::new(GameConfig::default());
+    game.round = fixture.round;
+    game.points = fixture.points;
+    let src = &fixture.round_state;
+    let round = &mut game.round_state;
+    round.dealer = src.dealer;
+    round.turn_16 = src.turn_16;
+    round.state = parse_state(&src.state);
+    round.hand = src.hand;
+    round.pile = src.pile;
+    round.field = src.field;
+    round.stock = src.stock.clone();
+    round.init_board = src.init_board;
+    round.show = src.show.clone();
+    round.koikoi = src.koikoi;
+    for (turn, rows) in src.card_log.iter().enumerate() {
+        for (row, values) in rows.iter().enumerate() {
+            round.card_log[turn][row].copy_from_slice(values);
+        }
+    }
+    game
+}
+
+fn features(game: &GameState) -> Vec<Vec<f32>> {
+    let observation = game.observation(game.round_state.turn_player());
+    let tensor = feature_tensor::<NdArray>(&observation, &NdArrayDevice::default());
+    assert_eq!(tensor.dims(), [1, 300, 48]);
+    tensor
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap()
+        .chunks(48)
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+fn block_of(row: usize) -> (&'static str, usize) {
+    let mut start = 0;
+    for (name, len) in BLOCKS {
+        if row < start + len {
+            return (name, row - start);
+        }
+        start += len;
+    }
+    unreachable!()
+}
+
+fn compare(name: &str, expected: &[Vec<f32>], actual: &[Vec<f32>]) -> Vec<String> {
+    let mut errors = vec!();
+    assert_eq!(expected.len(), 300, "{name}: expected features must have 300 rows");
+    for (row, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        for (col, (e, a)) in expected.iter().zip(actual).enumerate() {
+            if (e - a).abs() > TOLERANCE {
+                let (block, offset) = block_of(row);
+                errors.push(format!("{name}: row {row} ({block} +{offset}), card {col}: expected {e}, got {a}"));
+            }
+        }
+    }
+    errors
+}
+
+#[test]
+#[ignore = "needs the fixtures written by dump_features.py in tests/fixtures/features"]
+fn python_fixtures() {
+    let entries = fs::read_dir(Path::new(FIXTURE_DIR))
+        .unwrap_or_else(|err| panic!("{FIXTURE_DIR}: {err}, run dump_features.py to write the fixtures"));
+    let mut paths: Vec<_> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    assert!(!paths.is_empty(), "no fixture in {FIXTURE_DIR}, run dump_features.py to write them");
+    paths.sort();
+
+    let mut errors = vec!();
+    for path in &paths {
+        let fixture: Fixture = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let game = game_from_fixture(&fixture);
+        let name = path.file_name().unwrap().to_string_lossy();
+        errors.extend(compare(&name, &fixture.features, &features(&game)));
+    }
+    assert!(errors.is_empty(), "{} mismatches:\n{}", errors.len(), errors[..errors.len().min(50)].join("\n"));
+}
+
+#[test]
+fn feature_layout() {
+    let game = GameState::new(GameConfig::default());
+    let f = features(&game);
+
+    assert_eq!(BLOCKS.iter().map(|(_, len)| len).sum::<usize>(), 300);
+    assert!(f[..17].iter().flatten().all(|&x| x == 0.));
+
+    // one row per month in the suit block
+    for month in 0..12 {
+        let row = &f[150 + month];
+        for (col, &x) in row.iter().enumerate() {
+            assert_eq!(x, if col / 4 == month { 1. } else { 0. });
+        }
+    }
+
+    // the first card key of the yaku status block is the crane
+    assert_eq!(f[137][0], 1.);
+    assert_eq!(f[137].iter().sum::<f32>(), 1.);
+
+    // the hand shows both in the initial and in the current position
+    assert_eq!(f[162], f[165]);
+    assert_eq!(f[162].iter().sum::<f32>(), 8.);
+    assert_eq!(f[163].iter().sum::<f32>(), 8.);
+
+    // nothing is logged and nothing is shown before the first discard
+    assert!(f[170..].iter().flatten().all(|&x| x == 0.));
+}
@@ -0,0 +1,63 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rust_burn_test::game::{Action, Card, CardSet, Deal, GameError, Player, RoundState, State};
+use rust_burn_test::rules::RuleSet;
+
+/// Deals `hands` in order, the field and the stock taking the other cards.
+fn deal(hands: [&[(u8, u8)]; 2]) -> Deal {
+    let hands = hands.map(|hand| hand.iter().map(|&(month, rank)| Card::new(month, rank)).collect::<Vec<_>>());
+    let dealt: CardSet = hands.iter().flatten().copied().collect();
+    assert_eq!(dealt.len(), 16);
+    let mut rest: Vec<_> = (CardSet::ALL - dealt).into_iter().collect();
+    // one card of each month on the field
+    rest.sort_by_key(|card| (card.rank(), card.month()));
+    let field = rest.drain(..8).collect();
+    Deal { hands, field, stock: rest }
+}
+
+const TESHI: &[(u8, u8)] = &[(1,1), (1,2), (1,3), (1,4), (2,1), (3,1), (4,1), (5,1)];
+const KUTTSUKI: &[(u8, u8)] = &[(6,1), (6,2), (7,1), (7,2), (8,1), (8,2), (9,1), (9,2)];
+const PLAIN: &[(u8, u8)] = &[(2,2), (2,3), (3,2), (4,2), (5,2), (10,2), (11,2), (12,2)];
+
+#[test]
+fn instant_wins_end_the_round() {
+    let rules = RuleSet { instant_wins: true, ..RuleSet::default() };
+    let mut round = RoundState::from_deal(Player::First, rules, &deal([PLAIN, KUTTSUKI]));
+    assert_eq!(round.state, State::InstantWin);
+    assert_eq!((round.instant_win(Player::First), round.instant_win(Player::Second)), (None, Some("Kuttsuki")));
+    assert_eq!(round.legal_actions(), vec![Action::Continue]);
+    assert_eq!(round.apply(Action::Draw), Err(GameError::WrongPhase(State::InstantWin)));
+    assert_eq!(round.round_points(Player::Second), None);
+    round.apply(Action::Continue).unwrap();
+    assert_eq!(round.state, State::RoundOver);
+    assert_eq!(round.round_points(Player::Second), Some(6));
+    assert_eq!(round.round_points(Player::First), Some(-6));
+
+    // the dealer wins when both hands do
+    let mut round = RoundState::from_deal(Player::Second, rules, &deal([TESHI, KUTTSUKI]));
+    assert_eq!(round.instant_win(Player::First), Some("Teshi"));
+    round.apply(Action::Continue).unwrap();
+    assert_eq!(round.round_points(Player::Second), Some(6));
+
+    let round = RoundState::from_deal(Player::First, RuleSet::default(), &deal([TESHI, KUTTSUKI]));
+    assert_eq!(round.state, State::Discard);
+    assert_eq!(round.instant_win(Player::First), None);
+}
+
+#[test]
+fn deals_with_four_cards_of_a_month() {
+    let four_of_month = |cards: CardSet| (1..13).any(|month| CardSet::month(month).is_subset(cards));
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut instant_wins = 0;
+    for _ in 0..2000 {
+        let round = RoundState::new(Player::First, RuleSet::default(), &mut rng);
+        assert_eq!(round.state, State::Discard);
+        assert!(!round.hand.into_iter().chain([round.field]).any(four_of_month));
+
+        let round = RoundState::new(Player::First, RuleSet::nintendo(), &mut rng);
+        assert!(!four_of_month(round.field));
+        let won = Player::BOTH.map(|player| round.instant_win(player).is_some());
+        assert_eq!(round.state == State::InstantWin, won.contains(&true));
+        instant_wins += won.contains(&true) as usize;
+    }
+    assert!(instant_wins > 0);
+}
@@ -0,0 +1,78 @@
+use burn::backend::NdArray;
+use rust_burn_test::agent::Sampling;
+use rust_burn_test::arena::{AgentSpec, Arena, RandomAgent, SpecError};
+use rust_burn_test::game::{GameConfig, GameObservation, GameState};
+use rust_burn_test::mcts::{Ismcts, IsmctsConfig};
+use rust_burn_test::rules::RuleSet;
+
+fn observe(game: &GameState) -> GameObservation {
+    game.observation(game.round_state.turn_player())
+}
+
+fn search(iterations: usize) -> Ismcts {
+    Ismcts::new(IsmctsConfig { iterations, ..IsmctsConfig::default() }).with_seed(3)
+}
+
+#[test]
+fn search_visits_the_legal_actions() {
+    let game = GameState::new(GameConfig::default());
+    let visits = search(100).search(&observe(&game));
+    let mut actions: Vec<_> = visits.iter().map(|&(action, _)| action).collect();
+    let mut legal_actions = game.legal_actions();
+    actions.sort_by_key(|action| format!("{action:?}"));
+    legal_actions.sort_by_key(|action| format!("{action:?}"));
+    assert_eq!(actions, legal_actions);
+    // the first iteration expands the root
+    assert_eq!(visits.iter().map(|&(_, visits)| visits).sum::<u32>(), 99);
+}
+
+#[test]
+fn search_ignores_the_hidden_cards() {
+    let game = GameState::new(GameConfig::default());
+    let opponent = game.round_state.turn_player().opponent();
+    let mut other = game.clone();
+    let round = &mut other.round_state;
+    let hidden = round.hand[opponent].iter().next().unwrap();
+    round.hand[opponent].remove(hidden);
+    round.hand[opponent].insert(round.stock[0]);
+    round.stock[0] = hidden;
+    round.stock.reverse();
+    assert_ne!(game.round_state.hand[opponent], other.round_state.hand[opponent]);
+    assert_eq!(search(50).search(&observe(&game)), search(50).search(&observe(&other)));
+}
+
+#[test]
+fn search_beats_random() {
+    let config = GameConfig { rules: RuleSet { round_total: 1, ..RuleSet::default() }, ..GameConfig::default() };
+    let report = Arena::new(20, config).play(&mut search(100), &mut RandomAgent::new(0));
+    assert!(report.wins > report.losses, "{report}");
+}
+
+#[test]
+fn ismcts_specs() {
+    let spec: AgentSpec = "ismcts:50@models:rl".parse().unwrap();
+    assert_eq!(spec, AgentSpec::Ismcts { iterations: 50, prior: Some(("models".into(), "rl".to_string())) });
+    assert_eq!(spec.to_string(), "ismcts:50@models:rl");
+    assert_eq!("ismcts".parse::<AgentSpec>().unwrap(), AgentSpec::Ismcts { iterations: 500, prior: None });
+    let mut agent = AgentSpec::Ismcts { iterations: 10, prior: None }
+        .build::<NdArray>(Sampling::Argmax, 0, &Default::default())
+        .unwrap();
+    let game = GameState::new(GameConfig::default());
+    assert!(game.legal_actions().contains(&agent.act(&observe(&game))));
+}
+
+#[test]
+fn malformed_ismcts_specs() {
+    let iterations = |n: &str| Err(SpecError::Iterations(n.to_string()));
+    assert_eq!("ismcts:0".parse::<AgentSpec>(), iterations("0"));
+    assert_eq!("ismcts:0@models:rl".parse::<AgentSpec>(), iterations("0"));
+    assert_eq!("ismcts:".parse::<AgentSpec>(), iterations(""));
+    assert_eq!("ismcts:foo".parse::<AgentSpec>(), iterations("foo"));
+    assert_eq!("ismcts:-3".parse::<AgentSpec>(), iterations("-3"));
+    assert_eq!("ismcts:50@".parse::<AgentSpec>(), Err(SpecError::Prior("ismcts:50@".to_string())));
+    // other names are checkpoint directories
+    assert_eq!(
+        "ismcts-models:rl".parse::<AgentSpec>().unwrap(),
+        AgentSpec::Checkpoint { dir: "ismcts-models".into(), tag: "rl".to_string() },
+    );
+}
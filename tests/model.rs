@@ -0,0 +1,277 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use burn::backend::{ndarray::NdArrayDevice, NdArray};
+use burn::config::Config;
+use burn::module::Module;
+use burn::record::{FullPrecisionSettings, PrettyJsonFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use candle_core::{DType, Device, Tensor as CandleTensor};
+use rust_burn_test::model::{DiscardModel, KoiKoiNet, KoiKoiNetConfig, LoadError, MpkRecorder};
+use serde_json::Value;
+
+fn tiny_net() -> KoiKoiNetConfig {
+    KoiKoiNetConfig::new().with_n_emb(8).with_n_fw(16).with_n_heads(2).with_n_layers(1)
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("koikoi-model-{}-{name}", std::process::id()))
+}
+
+/// The parameters of `model` by their burn keys, read back from its JSON record.
+fn burn_tensors(model: DiscardModel<NdArray>) -> HashMap<String, CandleTensor> {
+    fn collect(prefix: &str, value: &Value, tensors: &mut HashMap<String, CandleTensor>) {
+        let map: Vec<(String, &Value)> = match value {
+            Value::Object(map) if map.contains_key("param") => {
+                let param = &map["param"];
+                let shape: Vec<usize> = serde_json::from_value(param["shape"].clone()).unwrap();
+                let bytes: Vec<u8> = serde_json::from_value(param["bytes"].clone()).unwrap();
+                let data = bytes.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+                tensors.insert(prefix.to_string(), CandleTensor::from_vec(data, shape, &Device::Cpu).unwrap());
+                return;
+            }
+            Value::Object(map) => map.iter().map(|(key, value)| (key.clone(), value)).collect(),
+            Value::Array(values) => values.iter().enumerate().map(|(i, value)| (i.to_string(), value)).collect(),
+            _ => return,
+        };
+        for (key, value) in map {
+            let key = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+            collect(&key, value, tensors);
+        }
+    }
+    let path = temp_path("burn");
+    model.save_file(&path, &PrettyJsonFileRecorder::<FullPrecisionSettings>::new()).unwrap();
+    let path = path.with_extension("json");
+    let record: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    let mut tensors = HashMap::new();
+    collect("", &record["item"], &mut tensors);
+    tensors
+}
+
+/// The state dict PyTorch would save for the weights of `model`.
+fn pytorch_tensors(model: DiscardModel<NdArray>) -> HashMap<String, CandleTensor> {
+    let mut burn = burn_tensors(model);
+    let mut tensors = HashMap::new();
+    let mut take = |key: &str| burn.remove(key).unwrap_or_else(|| panic!("no {key}"));
+    for key in ["encoder_block.f1", "encoder_block.f2", "out"] {
+        for kind in ["weight", "bias"] {
+            tensors.insert(format!("{key}.{kind}"), take(&format!("{key}.{kind}")));
+        }
+    }
+    for layer in (0..).map(|i| format!("encoder_block.attn_encoder.layers.{i}")) {
+        if !burn.contains_key(&format!("{layer}.norm_1.gamma")) {
+            break;
+        }
+        let mut take = |key: &str| burn.remove(&format!("{layer}.{key}")).unwrap_or_else(|| panic!("no {layer}.{key}"));
+        let mut linear = |key: &str| (take(&format!("{key}.weight")).t().unwrap(), take(&format!("{key}.bias")));
+        let (mut weights, mut biases) = (vec![], vec![]);
+        for name in ["query", "key", "value"] {
+            let (weight, bias) = linear(&format!("mha.{name}"));
+            weights.push(weight);
+            biases.push(bias);
+        }
+        tensors.insert(format!("{layer}.self_attn.in_proj_weight"), CandleTensor::cat(&weights, 0).unwrap());
+        tensors.insert(format!("{layer}.self_attn.in_proj_bias"), CandleTensor::cat(&biases, 0).unwrap());
+        for (burn_key, key) in [("mha.output", "self_attn.out_proj"), ("pwff.linear_inner", "linear1"), ("pwff.linear_outer", "linear2")] {
+            let (weight, bias) = linear(burn_key);
+            tensors.insert(format!("{layer}.{key}.weight"), weight.contiguous().unwrap());
+            tensors.insert(format!("{layer}.{key}.bias"), bias);
+        }
+        for (burn_key, key) in [("norm_1", "norm1"), ("norm_2", "norm2")] {
+            tensors.insert(format!("{layer}.{key}.weight"), take(&format!("{burn_key}.gamma")));
+            tensors.insert(format!("{layer}.{key}.bias"), take(&format!("{burn_key}.beta")));
+        }
+    }
+    assert!(burn.is_empty(), "unknown keys {:?}", burn.keys());
+    tensors
+}
+
+/// Saves `tensors` as a safetensors checkpoint of the tiny net.
+fn save_checkpoint(name: &str, tensors: &HashMap<String, CandleTensor>) -> PathBuf {
+    let path = temp_path(name).with_extension("safetensors");
+    candle_core::safetensors::save(tensors, &path).unwrap();
+    tiny_net().save(KoiKoiNetConfig::checkpoint_path(&path)).unwrap();
+    path
+}
+
+fn remove_checkpoint(path: &PathBuf) {
+    fs::remove_file(path).unwrap();
+    fs::remove_file(KoiKoiNetConfig::checkpoint_path(path)).unwrap();
+}
+
+#[test]
+fn pytorch_weights_are_adapted() {
+    let device = NdArrayDevice::default();
+    let model = tiny_net().init_discard::<NdArray>(&device);
+    let path = save_checkpoint("adapted", &pytorch_tensors(model.clone()));
+    let loaded = DiscardModel::<NdArray>::load_safetensors(&path, &device);
+    remove_checkpoint(&path);
+
+    let x = Tensor::<NdArray, 3>::random([2, 300, 48], Distribution::Default, &device);
+    let expected = model.forward(x.clone()).into_data();
+    loaded.unwrap().forward(x).into_data().assert_approx_eq(&expected, 5);
+}
+
+#[test]
+fn checkpoints_are_checked() {
+    let device = NdArrayDevice::default();
+    let tensors = pytorch_tensors(tiny_net().init_discard::<NdArray>(&device));
+    let load = |name: &str, edit: &dyn Fn(&mut HashMap<String, CandleTensor>)| {
+        let mut tensors = tensors.clone();
+        edit(&mut tensors);
+        let path = save_checkpoint(name, &tensors);
+        let result = DiscardModel::<NdArray>::load_safetensors(&path, &device);
+        remove_checkpoint(&path);
+        result.err()
+    };
+    let bias = "encoder_block.attn_encoder.layers.0.self_attn.in_proj_bias";
+
+    let err = load("missing", &|tensors| { tensors.remove(bias); });
+    assert!(matches!(err, Some(LoadError::MissingKeys(keys)) if keys == [bias]));
+
+    let err = load("unexpected", &|tensors| {
+        tensors.insert("extra.weight".to_string(), CandleTensor::zeros(2, DType::F32, &Device::Cpu).unwrap());
+    });
+    assert!(matches!(err, Some(LoadError::UnexpectedKeys(keys)) if keys == ["extra.weight"]));
+
+    let err = load("shape", &|tensors| {
+        tensors.insert(bias.to_string(), CandleTensor::zeros(23, DType::F32, &Device::Cpu).unwrap());
+    });
+    assert!(matches!(
+        err,
+        Some(LoadError::ShapeMismatch { key, expected, actual }) if key == bias && expected == [24] && actual == [23]
+    ));
+
+    let missing = DiscardModel::<NdArray>::load_safetensors(temp_path("none.safetensors"), &device);
+    assert!(matches!(missing, Err(LoadError::Read(_))));
+}
+
+#[test]
+fn net_configs() {
+    let path = temp_path("net.config.json");
+    tiny_net().save(&path).unwrap();
+    let read = KoiKoiNetConfig::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read.to_string(), tiny_net().to_string());
+    assert_eq!((read.n_input, read.n_emb, read.n_fw, read.n_heads, read.n_layers), (300, 8, 16, 2, 1));
+
+    let default = KoiKoiNetConfig::for_checkpoint(&temp_path("none.mpk")).unwrap();
+    assert_eq!(default.to_string(), KoiKoiNetConfig::new().to_string());
+}
+
+/// Saves `model` as a burn record with the tiny net config, then loads it back.
+fn mpk_round_trip<M: KoiKoiNet<NdArray>>(name: &str, model: M) -> M {
+    let path = temp_path(name).with_extension("mpk");
+    model.save_file(&path, &MpkRecorder::new()).unwrap();
+    tiny_net().save(KoiKoiNetConfig::checkpoint_path(&path)).unwrap();
+    let loaded = M::load_mpk(&path, &NdArrayDevice::default());
+    remove_checkpoint(&path);
+    loaded.unwrap()
+}
+
+#[test]
+fn checkpoints_of_other_configs() {
+    let device = NdArrayDevice::default();
+    let x = Tensor::<NdArray, 3>::random([2, 300, 48], Distribution::Default, &device);
+    let model = tiny_net().init_pick::<NdArray>(&device);
+    let loaded = mpk_round_trip("pick", model.clone());
+    loaded.forward(x.clone()).into_data().assert_eq(&model.forward(x.clone()).into_data(), true);
+    let model = tiny_net().init_koikoi::<NdArray>(&device);
+    let loaded = mpk_round_trip("koikoi", model.clone());
+    loaded.forward(x.clone()).into_data().assert_eq(&model.forward(x).into_data(), true);
+}
+
+fn values(tensor: &CandleTensor) -> Vec<f32> {
+    tensor.flatten_all().unwrap().to_vec1().unwrap()
+}
+
+/// `y = x w^T + b` for the rows `x` of `input`, as `nn.Linear` (or a 1x1 `nn.Conv1d`).
+fn linear(input: &[Vec<f32>], weight: &[f32], bias: &[f32]) -> Vec<Vec<f32>> {
+    let n_in = input[0].len();
+    input
+        .iter()
+        .map(|x| bias.iter().enumerate().map(|(o, b)| b + (0..n_in).map(|i| weight[o * n_in + i] * x[i]).sum::<f32>()).collect())
+        .collect()
+}
+
+fn normalize(x: &[f32], eps: f32) -> Vec<f32> {
+    let mean = x.iter().sum::<f32>() / x.len() as f32;
+    let variance = x.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / x.len() as f32;
+    x.iter().map(|v| (v - mean) / (variance + eps).sqrt()).collect()
+}
+
+fn transpose(x: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    (0..x[0].len()).map(|j| x.iter().map(|row| row[j]).collect()).collect()
+}
+
+/// The forward pass of the PyTorch discard network of KoiKoi-AI on one game of `[300][48]`
+/// features, written after its `forward`: the features of the 48 cards are permuted to
+/// `[seq, batch, emb]` for `nn.TransformerEncoder` (post-norm, with the default `relu`).
+fn pytorch_forward(tensors: &HashMap<String, CandleTensor>, net: &KoiKoiNetConfig, features: &[Vec<f32>]) -> Vec<f32> {
+    let w = |key: &str| values(&tensors[key]);
+    let cards = transpose(features);
+    let hidden = linear(&cards, &w("encoder_block.f1.weight"), &w("encoder_block.f1.bias"));
+    let hidden: Vec<Vec<f32>> = hidden.into_iter().map(|h| h.into_iter().map(|v| v.max(0.)).collect()).collect();
+    let emb = linear(&hidden, &w("encoder_block.f2.weight"), &w("encoder_block.f2.bias"));
+    // the layer norm of the encoder block runs over the cards
+    let mut x = transpose(&transpose(&emb).iter().map(|row| normalize(row, 1e-4)).collect::<Vec<_>>());
+
+    let (n_emb, n_heads) = (net.n_emb, net.n_heads);
+    let d = n_emb / n_heads;
+    for layer in 0..net.n_layers {
+        let w = |key: &str| w(&format!("encoder_block.attn_encoder.layers.{layer}.{key}"));
+        let qkv = linear(&x, &w("self_attn.in_proj_weight"), &w("self_attn.in_proj_bias"));
+        let mut attention = vec![vec![0.; n_emb]; x.len()];
+        for head in 0..n_heads {
+            let (q, k, v) = (head * d, n_emb + head * d, 2 * n_emb + head * d);
+            for (i, out) in attention.iter_mut().enumerate() {
+                let scores: Vec<f32> = qkv
+                    .iter()
+                    .map(|key| (0..d).map(|j| qkv[i][q + j] * key[k + j]).sum::<f32>() / (d as f32).sqrt())
+                    .collect();
+                let max = scores.iter().copied().fold(f32::MIN, f32::max);
+                let weights: Vec<f32> = scores.iter().map(|s| (s - max).exp()).collect();
+                let total: f32 = weights.iter().sum();
+                for (value, weight) in qkv.iter().zip(&weights) {
+                    for j in 0..d {
+                        out[head * d + j] += weight / total * value[v + j];
+                    }
+                }
+            }
+        }
+        let attention = linear(&attention, &w("self_attn.out_proj.weight"), &w("self_attn.out_proj.bias"));
+        let norm = |x: Vec<Vec<f32>>, key: &str| -> Vec<Vec<f32>> {
+            let (gamma, beta) = (w(&format!("{key}.weight")), w(&format!("{key}.bias")));
+            x.iter()
+                .map(|row| normalize(row, 1e-5).iter().enumerate().map(|(j, v)| v * gamma[j] + beta[j]).collect())
+                .collect()
+        };
+        let add = |a: &[Vec<f32>], b: &[Vec<f32>]| -> Vec<Vec<f32>> {
+            a.iter().zip(b).map(|(a, b)| a.iter().zip(b).map(|(a, b)| a + b).collect()).collect()
+        };
+        x = norm(add(&x, &attention), "norm1");
+        let inner = linear(&x, &w("linear1.weight"), &w("linear1.bias"));
+        let inner: Vec<Vec<f32>> = inner.into_iter().map(|h| h.into_iter().map(|v| v.max(0.)).collect()).collect();
+        let outer = linear(&inner, &w("linear2.weight"), &w("linear2.bias"));
+        x = norm(add(&x, &outer), "norm2");
+    }
+    linear(&x, &w("out.weight"), &w("out.bias")).into_iter().map(|logit| logit[0]).collect()
+}
+
+#[test]
+fn attention_runs_over_the_cards_of_each_game() {
+    let device = NdArrayDevice::default();
+    let net = tiny_net().with_n_layers(2);
+    let model = net.init_discard::<NdArray>(&device);
+    let tensors = pytorch_tensors(model.clone());
+    let x = Tensor::<NdArray, 3>::random([3, 300, 48], Distribution::Default, &device);
+    let logits = model.forward(x.clone()).into_data().to_vec::<f32>().unwrap();
+    let features = x.into_data().to_vec::<f32>().unwrap();
+    for (game, logits) in logits.chunks(48).enumerate() {
+        let game: Vec<Vec<f32>> = features[game * 300 * 48..(game + 1) * 300 * 48].chunks(48).map(<[f32]>::to_vec).collect();
+        let expected = pytorch_forward(&tensors, &net, &game);
+        for (logit, expected) in logits.iter().zip(expected) {
+            assert!((logit - expected).abs() < 1e-4, "{logit} != {expected}");
+        }
+    }
+}
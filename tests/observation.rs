@@ -0,0 +1,57 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rust_burn_test::game::{GameConfig, GameState};
+use rust_burn_test::game_tensor::feature_array;
+
+#[test]
+fn hidden_cards_are_masked() {
+    let mut game = GameState::new(GameConfig::default());
+    for _ in 0..7 {
+        let action = game.legal_actions()[0];
+        game.step(action).unwrap();
+    }
+    let player = game.round_state.turn_player();
+    let observation = game.observation(player);
+    let view = &observation.round_state;
+    assert_eq!(observation.player(), player);
+    assert!(view.round.hand[player.opponent()].is_empty());
+    assert!(view.round.stock.is_empty());
+    assert_eq!(view.round.hand[player], game.round_state.hand[player]);
+    assert_eq!(view.opponent_hand_len, game.round_state.hand[player.opponent()].len());
+    assert_eq!(view.unseen, game.round_state.unseen_cards(player));
+    assert_eq!(observation.legal_actions(), game.legal_actions());
+    assert!(game.observation(player.opponent()).legal_actions().is_empty());
+
+    // the features do not depend on the hidden cards
+    let mut other = game.clone();
+    let round = &mut other.round_state;
+    let hidden = round.hand[player.opponent()].iter().next().unwrap();
+    round.hand[player.opponent()].remove(hidden);
+    round.hand[player.opponent()].insert(round.stock[0]);
+    round.stock[0] = hidden;
+    round.stock.reverse();
+    assert_eq!(feature_array(&observation), feature_array(&other.observation(player)));
+}
+
+#[test]
+fn sampled_worlds_match_the_observation() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut game = GameState::new(GameConfig { seed: 5, ..GameConfig::default() });
+    while !game.game_over {
+        let player = game.round_state.turn_player();
+        let observation = game.observation(player);
+        let world = observation.sample_world(&mut rng);
+        let (real, sampled) = (&game.round_state, &world.round_state);
+        assert_eq!(sampled.hand[player], real.hand[player]);
+        assert_eq!(sampled.hand[player.opponent()].len(), real.hand[player.opponent()].len());
+        assert_eq!(sampled.stock.len(), real.stock.len());
+        assert_eq!(sampled.unseen_cards(player), observation.round_state.unseen);
+        assert_eq!(sampled.pile, real.pile);
+        assert_eq!(sampled.field, real.field);
+        assert_eq!((world.round, world.points), (game.round, game.points));
+        assert_eq!(world.legal_actions(), game.legal_actions());
+
+        let action = *game.legal_actions().last().unwrap();
+        game.step(action).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+use rust_burn_test::game::{GameConfig, GameState, Player};
+
+#[test]
+fn players_index_the_pairs() {
+    assert_eq!(Player::First.opponent(), Player::Second);
+    assert_eq!(Player::Second.opponent(), Player::First);
+    let mut points = [1, 2];
+    points[Player::Second] += 1;
+    assert_eq!(points[Player::First], 1);
+    assert_eq!(points[Player::Second], 3);
+    assert_eq!(Player::BOTH.map(Player::index), [0, 1]);
+}
+
+#[test]
+fn players_are_serialized_as_indices() {
+    assert_eq!(serde_json::to_string(&Player::Second).unwrap(), "1");
+    assert_eq!(serde_json::from_str::<Player>("0").unwrap(), Player::First);
+    assert!(serde_json::from_str::<Player>("2").is_err());
+}
+
+#[test]
+fn the_dealer_plays_first() {
+    for dealer in Player::BOTH {
+        let game = GameState::new(GameConfig { init_dealer: dealer, ..GameConfig::default() });
+        assert_eq!(game.round_state.dealer, dealer);
+        assert_eq!(game.round_state.turn_player(), dealer);
+    }
+}
@@ -0,0 +1,107 @@
+use rust_burn_test::arena::{Arena, GreedyAgent, RandomAgent};
+use rust_burn_test::game::{CardSet, GameConfig, Player, RoundState};
+use rust_burn_test::python_log::import_game;
+use rust_burn_test::record::GameRecord;
+use rust_burn_test::rules::RuleSet;
+use serde_json::{json, Value};
+
+fn cards(cards: CardSet) -> Value {
+    cards.iter().map(|card| json!([card.month(), card.rank()])).collect()
+}
+
+/// The log of a round in the layout of the Python environment, from its state before the last action.
+fn round_log(round: &RoundState, first: &RoundState, winner: Option<Player>) -> Value {
+    let names = ["discard", "discardPairing", "discardPick", "discardCollect", "draw", "drawPairing", "drawPick", "drawCollect"];
+    let card_log: serde_json::Map<_, _> = round.card_log
+        .iter()
+        .enumerate()
+        .map(|(turn, rows)| {
+            let rows: serde_json::Map<_, _> = names.iter().zip(rows).map(|(name, row)| (name.to_string(), json!(row.to_vec()))).collect();
+            ((turn + 1).to_string(), Value::Object(rows))
+        })
+        .collect();
+    json!({
+        "basic": {
+            "dealer": round.dealer.index() + 1,
+            "initHand1": cards(first.hand[0]),
+            "initHand2": cards(first.hand[1]),
+            "initBoard": cards(round.init_board),
+        },
+        "card_log_dict": card_log,
+        "koikoi": {"1": round.koikoi[0].to_vec(), "2": round.koikoi[1].to_vec()},
+        "winner": winner.map(|player| player.index() + 1),
+    })
+}
+
+fn python_log(record: &GameRecord) -> Value {
+    let mut firsts = vec!();
+    let mut lasts: Vec<RoundState> = vec!();
+    let mut points = vec!();
+    record.replay_with(|_, game| {
+        if lasts.len() < game.round {
+            firsts.push(game.round_state.clone());
+            lasts.push(game.round_state.clone());
+            points.push(game.points);
+        }
+        lasts[game.round - 1] = game.round_state.clone();
+    }).unwrap();
+    let game = record.replay().unwrap();
+    points.push(game.points);
+    let rounds: serde_json::Map<_, _> = (0..lasts.len())
+        .map(|i| {
+            let winner = Player::BOTH.into_iter().find(|&player| points[i + 1][player] > points[i][player]);
+            (format!("round{}", i + 1), round_log(&lasts[i], &firsts[i], winner))
+        })
+        .collect();
+    Value::Object(rounds)
+}
+
+#[test]
+fn imported_logs_replay_the_matches() {
+    let config = GameConfig { rules: RuleSet { round_total: 3, ..RuleSet::default() }, ..GameConfig::default() };
+    let (_, records) = Arena::new(6, config.clone()).play_recorded(&mut GreedyAgent, &mut RandomAgent::new(3));
+    for record in &records {
+        let imported = import_game(&python_log(record), &config).unwrap();
+        let actions = |record: &GameRecord| record.actions.iter().map(|a| (a.player, a.action)).collect::<Vec<_>>();
+        assert_eq!(actions(&imported), actions(record));
+        assert_eq!(imported.replay().unwrap().points, record.replay().unwrap().points);
+    }
+}
+
+#[test]
+fn divergences_are_flagged() {
+    let config = GameConfig { rules: RuleSet { round_total: 1, ..RuleSet::default() }, ..GameConfig::default() };
+    let (_, records) = Arena::new(10, config.clone()).play_recorded(&mut GreedyAgent, &mut RandomAgent::new(5));
+    let log = records.iter().map(python_log).find(|log| log["round1"]["winner"].is_u64()).expect("a round with a winner");
+
+    let mut wrong_winner = log.clone();
+    let winner = wrong_winner["round1"]["winner"].as_u64().unwrap();
+    wrong_winner["round1"]["winner"] = json!(3 - winner);
+    let err = import_game(&wrong_winner, &config).unwrap_err();
+    assert_eq!((err.round, err.turn), (1, None));
+
+    let mut wrong_pairing = log.clone();
+    let row = &mut wrong_pairing["round1"]["card_log_dict"]["1"]["discardPairing"];
+    let fake = if row[47] == json!(0.) { 1. } else { 0. };
+    row[47] = json!(fake);
+    let err = import_game(&wrong_pairing, &config).unwrap_err();
+    assert_eq!((err.round, err.turn), (1, Some(1)));
+    assert!(err.message.starts_with("discard pairing"), "{err}");
+}
+
+#[test]
+#[ignore = "needs logs of the Python environment in tests/fixtures/koikoi_ai"]
+fn python_logs_are_imported() {
+    let paths: Vec<_> = std::fs::read_dir("tests/fixtures/koikoi_ai")
+        .expect("tests/fixtures/koikoi_ai is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    assert!(!paths.is_empty(), "no log in tests/fixtures/koikoi_ai");
+    for path in paths {
+        let log: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        if let Err(err) = import_game(&log, &GameConfig::default()) {
+            panic!("{}: {err}", path.display());
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::env;
+use rust_burn_test::arena::{Arena, ArenaReport, GreedyAgent, RandomAgent};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::rating::{expected_score, RatingPool, DEFAULT_RATING};
+use rust_burn_test::rules::RuleSet;
+
+#[test]
+fn elo_updates_are_zero_sum() {
+    let mut pool = RatingPool::default();
+    assert!(pool.add("a", "greedy"));
+    assert!(pool.add("b", "random"));
+    assert!(!pool.add("a", "random"));
+    assert_eq!(pool.round_robin(), [(0, 1)]);
+
+    let report = ArenaReport { wins: 3, draws: 0, losses: 1, point_diffs: vec![5, 2, -3, 1] };
+    pool.record(0, 1, &report);
+    let [a, b] = [&pool.entries[0], &pool.entries[1]];
+    assert_eq!(a.rating - DEFAULT_RATING, pool.k_factor * 4. * (0.75 - 0.5));
+    assert_eq!(a.rating + b.rating, 2. * DEFAULT_RATING);
+    assert_eq!((b.wins, b.draws, b.losses), (1, 0, 3));
+    assert_eq!(pool.leaderboard()[0].name, "a");
+}
+
+#[test]
+fn long_runs_converge_to_their_score() {
+    let mut pool = RatingPool::default();
+    pool.add("a", "greedy");
+    pool.add("b", "random");
+    // a 60% score over 200 matches
+    let report = ArenaReport { wins: 110, draws: 20, losses: 70, point_diffs: vec![0; 200] };
+    pool.record(0, 1, &report);
+    let delta = pool.entries[0].rating - DEFAULT_RATING;
+    assert!((delta - pool.k_factor * 200. * 0.1).abs() < 1e-9);
+
+    // the same results again move the ratings towards the 60% score without overshooting
+    for _ in 0..200 {
+        let before = pool.entries[0].rating;
+        pool.record(0, 1, &report);
+        assert!(pool.entries[0].rating >= before);
+    }
+    let expected = expected_score(pool.entries[0].rating, pool.entries[1].rating);
+    assert!((expected - 0.6).abs() < 0.01, "{expected}");
+
+    pool.record(0, 1, &ArenaReport { wins: 0, draws: 0, losses: 0, point_diffs: vec![] });
+    assert!(pool.entries[0].rating.is_finite());
+}
+
+#[test]
+fn a_round_robin_rates_the_stronger_agents_higher() {
+    let mut pool = RatingPool::default();
+    pool.add("greedy", "greedy");
+    pool.add("random", "random");
+    let report = Arena::new(200, GameConfig::default()).play(&mut GreedyAgent, &mut RandomAgent::new(0));
+    pool.record(0, 1, &report);
+    let [greedy, random] = [&pool.entries[0], &pool.entries[1]];
+    assert!(greedy.rating - random.rating > 100., "{} against {}", greedy.rating, random.rating);
+}
+
+#[test]
+fn expected_scores() {
+    assert_eq!(expected_score(1500., 1500.), 0.5);
+    assert!((expected_score(1900., 1500.) - 10. / 11.).abs() < 1e-12);
+    assert!((expected_score(1700., 1600.) + expected_score(1600., 1700.) - 1.).abs() < 1e-12);
+}
+
+#[test]
+fn pool_is_saved_as_json() {
+    let path = env::temp_dir().join(format!("koikoi-ratings-{}.json", std::process::id()));
+    let mut pool = RatingPool::load(&path).unwrap();
+    assert!(pool.entries.is_empty());
+    pool.add("a", "checkpoints:rl");
+    assert!(pool.set_rules(RuleSet::nintendo()));
+    pool.save(&path).unwrap();
+    let loaded = RatingPool::load(&path).unwrap();
+    assert_eq!(loaded.entries.len(), 1);
+    assert_eq!(loaded.entries[0].agent, "checkpoints:rl");
+    assert_eq!(loaded.rules, RuleSet::nintendo());
+
+    // the pools saved without rules were rated under the KoiKoi-AI rules
+    std::fs::write(&path, r#"{"k_factor": 16, "entries": []}"#).unwrap();
+    let loaded = RatingPool::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.rules, RuleSet::default());
+}
+
+#[test]
+fn pools_do_not_mix_rules() {
+    let mut pool = RatingPool::default();
+    pool.add("a", "greedy");
+    pool.add("b", "random");
+    // the rules can change until a match is recorded
+    assert!(pool.set_rules(RuleSet::nintendo()));
+    assert!(pool.set_rules(RuleSet::default()));
+    pool.record(0, 1, &ArenaReport { wins: 1, draws: 0, losses: 0, point_diffs: vec![3] });
+    assert!(pool.set_rules(RuleSet::default()));
+    assert!(!pool.set_rules(RuleSet::nintendo()));
+    assert!(!pool.set_rules(RuleSet { round_total: 12, ..RuleSet::default() }));
+    assert_eq!(pool.rules, RuleSet::default());
+}
@@ -0,0 +1,45 @@
+use std::{env, fs};
+use rust_burn_test::arena::{Arena, GreedyAgent, RandomAgent};
+use rust_burn_test::game::{Action, GameConfig, GameError};
+use rust_burn_test::record::{append_records, read_records, ReplayError};
+use rust_burn_test::rules::RuleSet;
+
+#[test]
+fn records_replay_the_matches() {
+    let config = GameConfig { rules: RuleSet { round_total: 2, ..RuleSet::default() }, seed: 7, ..GameConfig::default() };
+    let (report, records) = Arena::new(4, config).play_recorded(&mut GreedyAgent, &mut RandomAgent::new(1));
+    for (i, record) in records.iter().enumerate() {
+        let game = record.replay().unwrap();
+        assert!(game.game_over);
+        let seat = i % 2;
+        assert_eq!(game.points[seat] - game.points[1 - seat], report.point_diffs[i]);
+    }
+
+    let path = env::temp_dir().join(format!("koikoi-records-{}.jsonl", std::process::id()));
+    append_records(&path, &records[..1]).unwrap();
+    append_records(&path, &records[1..]).unwrap();
+    let read = read_records(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read, records);
+}
+
+#[test]
+fn divergences_are_reported() {
+    let (_, records) = Arena::new(1, GameConfig::default()).play_recorded(&mut GreedyAgent, &mut GreedyAgent);
+
+    let mut record = records[0].clone();
+    record.actions[3].player = record.actions[3].player.opponent();
+    let expected = record.actions[3].player.opponent();
+    assert_eq!(
+        record.replay().err(),
+        Some(ReplayError::WrongPlayer { step: 3, expected, actual: record.actions[3].player }),
+    );
+
+    let mut record = records[0].clone();
+    let card = record.state_at(0).unwrap().round_state.stock[0];
+    record.actions[0].action = Action::Discard(card);
+    assert_eq!(
+        record.replay().err(),
+        Some(ReplayError::Rejected { step: 0, error: GameError::CardNotInHand(card) }),
+    );
+}
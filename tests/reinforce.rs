@@ -0,0 +1,85 @@
+use burn::backend::{ndarray::NdArrayDevice, Autodiff, NdArray};
+use burn::data::dataloader::batcher::Batcher;
+use burn::optim::AdamConfig;
+use burn::tensor::{Tensor, TensorData};
+use rust_burn_test::agent::{Decision, NeuralAgent, Sampling};
+use rust_burn_test::arena::{Arena, GreedyAgent, RandomAgent};
+use rust_burn_test::game::{GameConfig, GameState};
+use rust_burn_test::model::KoiKoiNetConfig;
+use rust_burn_test::reinforce::{decision_samples, policy_gradient_loss, IterationLog, SelfPlay, SelfPlayConfig};
+use rust_burn_test::rules::RuleSet;
+use rust_burn_test::train::DecisionBatcher;
+
+#[test]
+fn rewards_are_the_point_deltas() {
+    let config = GameConfig { rules: RuleSet { round_total: 2, ..RuleSet::default() }, ..GameConfig::default() };
+    let (_, records) = Arena::new(2, config.clone()).play_recorded(&mut GreedyAgent, &mut RandomAgent::new(0));
+    for record in &records {
+        let game = record.replay().unwrap();
+        let total = game.points.map(|points| (points - config.init_point) as f32);
+        let samples = decision_samples(record, 1.).unwrap();
+        assert!(!samples.is_empty());
+        // with no discount the decisions of the first round are rewarded with the points of the match
+        let first_round = samples.iter().take_while(|sample| sample.reward == total[sample.player]);
+        assert!(first_round.count() > 0);
+        for sample in &samples {
+            assert!(sample.item.legal[sample.item.target]);
+        }
+
+        let round_samples = decision_samples(record, 0.).unwrap();
+        assert_eq!(round_samples.len(), samples.len());
+        let last = round_samples.last().unwrap();
+        assert_eq!(last.reward, samples.last().unwrap().reward);
+    }
+}
+
+#[test]
+fn policy_gradient_follows_the_advantages() {
+    let device = NdArrayDevice::default();
+    let (_, records) = Arena::new(2, GameConfig::default()).play_recorded(&mut GreedyAgent, &mut GreedyAgent);
+    let sample = decision_samples(&records[0], 0.)
+        .unwrap()
+        .into_iter()
+        .find(|sample| sample.decision == Decision::KoiKoi)
+        .unwrap();
+    let mut batch = DecisionBatcher::<NdArray>::new(device).batch(vec![sample.item]);
+    batch.legal = Tensor::from_data(TensorData::new(vec![true, true], [1, 2]), &device);
+    batch.targets = Tensor::from_data([1], &device);
+
+    let logits = Tensor::<NdArray, 2>::from_data([[0., 0.]], &device);
+    let advantages = Tensor::from_data([2.], &device);
+    let (loss, entropy) = policy_gradient_loss(logits, batch, advantages, 0.5);
+    let (loss, entropy): (f32, f32) = (loss.into_scalar(), entropy.into_scalar());
+    assert!((entropy - 2f32.ln()).abs() < 1e-5);
+    assert!((loss - (2. * 2f32.ln() - 0.5 * 2f32.ln())).abs() < 1e-5);
+}
+
+#[test]
+fn self_play_iterations_update_the_networks() {
+    type B = Autodiff<NdArray>;
+    let device = NdArrayDevice::default();
+    let net = KoiKoiNetConfig::new().with_n_emb(8).with_n_fw(16).with_n_heads(2).with_n_layers(1);
+    let agent = NeuralAgent::<B>::new(
+        net.init_discard(&device),
+        net.init_pick(&device),
+        net.init_koikoi(&device),
+        Sampling::Argmax,
+        device,
+    );
+    let config = SelfPlayConfig::new(AdamConfig::new()).with_matches(2).with_rules(RuleSet { round_total: 1, ..RuleSet::default() }).with_learning_rate(1e-2);
+    let mut trainer = SelfPlay::new(agent, net, config, device);
+
+    let game = GameState::new(GameConfig::default());
+    let game = game.observation(game.round_state.turn_player());
+    let before = trainer.agent(Sampling::Temperature(1.)).policy(&game);
+    let log = trainer.iterate();
+    assert_eq!(log.iteration, 1);
+    assert_eq!(trainer.iterations(), 1);
+    assert!(log.positions[0] > 0);
+    assert!(log.loss.iter().chain(&log.entropy).all(|x| x.is_finite()));
+    assert_ne!(trainer.agent(Sampling::Temperature(1.)).policy(&game), before);
+
+    let row = log.csv_row();
+    assert_eq!(row.split(',').count(), IterationLog::CSV_HEADER.split(',').count());
+    assert!(row.ends_with(",,"));
+}
@@ -0,0 +1,267 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rust_burn_test::game::{
+    Action, Card, CardLog, CardSet, Deal, GameConfig, GameError, GameState, Player, RoundState, State,
+    LOG_COLLECT, LOG_DISCARD, LOG_DRAW, LOG_PAIRING, LOG_PICK,
+};
+use rust_burn_test::rules::RuleSet;
+
+fn cards(cards: &[(u8, u8)]) -> Vec<Card> {
+    cards.iter().map(|&(month, rank)| Card::new(month, rank)).collect()
+}
+
+/// A round dealt by the first player, whose pile already holds the crane and the curtain.
+/// Discarding the wisteria pairs with two field cards, then the moon is drawn and pairs with
+/// one field card, which makes three lights.
+fn round() -> RoundState {
+    let hands = [
+        cards(&[(4,1), (5,1), (6,1), (7,1), (9,2), (10,1), (11,1), (12,1)]),
+        cards(&[(2,1), (2,2), (5,2), (6,2), (7,2), (10,2), (11,2), (12,2)]),
+    ];
+    let field = cards(&[(4,2), (4,3), (8,2), (5,3), (6,3), (7,3), (9,3), (10,3)]);
+    let piled = CardSet::of(&cards(&[(1,1), (3,1)]));
+    let top = Card::new(8,1);
+    let dealt: CardSet = hands.iter().chain([&field]).flatten().copied().collect();
+    let mut stock: Vec<_> = (CardSet::ALL - dealt - piled - CardSet::from(top)).into_iter().collect();
+    stock.push(top);
+    let mut round = RoundState::from_deal(Player::First, RuleSet::default(), &Deal { hands, field, stock });
+    round.pile[Player::First] = piled;
+    round
+}
+
+type Snapshot = ([CardSet; 2], [CardSet; 2], CardSet, Vec<Card>, Vec<Card>, State, usize, Vec<Action>, Box<CardLog>);
+
+fn snapshot(round: &RoundState) -> Snapshot {
+    (
+        round.hand,
+        round.pile,
+        round.field,
+        round.stock.clone(),
+        round.show.clone(),
+        round.state,
+        round.turn_16,
+        round.legal_actions(),
+        Box::new(round.card_log),
+    )
+}
+
+/// Applies `action`, which must be rejected with `error` and leave the round untouched.
+fn reject(round: &mut RoundState, action: Action, error: GameError) {
+    let before = snapshot(round);
+    assert_eq!(round.apply(action), Err(error));
+    assert_eq!(snapshot(round), before);
+}
+
+fn has_four_of_month(cards: CardSet) -> bool {
+    (1..13).any(|month| CardSet::month(month).is_subset(cards))
+}
+
+#[test]
+fn deals_are_seeded() {
+    let deal = |seed| {
+        let round = RoundState::new(Player::First, RuleSet::default(), &mut StdRng::seed_from_u64(seed));
+        (round.hand, round.field, round.stock)
+    };
+    assert_eq!(deal(3), deal(3));
+    assert_ne!(deal(3), deal(4));
+
+    let (hand, field, stock) = deal(3);
+    assert_eq!(hand.map(CardSet::len), [8, 8]);
+    assert_eq!(field.len(), 8);
+    assert_eq!(stock.len(), 24);
+    let stock: CardSet = stock.into_iter().collect();
+    assert_eq!(hand[0] | hand[1] | field | stock, CardSet::ALL);
+}
+
+#[test]
+fn four_cards_of_a_month_are_redealt() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..2000 {
+        let round = RoundState::new(Player::First, RuleSet::default(), &mut rng);
+        assert!(!round.hand.into_iter().chain([round.field]).any(has_four_of_month));
+        assert_eq!(round.field, round.init_board);
+    }
+}
+
+#[test]
+fn actions_of_each_state() {
+    let mut round = round();
+    assert_eq!(round.state, State::Discard);
+    assert_eq!(round.legal_actions(), round.hand[Player::First].iter().map(Action::Discard).collect::<Vec<_>>());
+    assert_eq!(round.legal_actions().len(), 8);
+
+    round.apply(Action::Discard(Card::new(4,1))).unwrap();
+    assert_eq!(round.state, State::DiscardPick);
+    assert_eq!(round.legal_actions(), vec![Action::Pick(Card::new(4,2)), Action::Pick(Card::new(4,3))]);
+
+    round.apply(Action::Pick(Card::new(4,3))).unwrap();
+    assert_eq!(round.state, State::Draw);
+    assert_eq!(round.legal_actions(), vec![Action::Draw]);
+    assert_eq!(round.pile[Player::First], CardSet::of(&cards(&[(1,1), (3,1), (4,1), (4,3)])));
+    assert_eq!(round.field & CardSet::month(4), CardSet::from(Card::new(4,2)));
+
+    round.apply(Action::Draw).unwrap();
+    assert_eq!(round.state, State::DrawPick);
+    assert_eq!(round.legal_actions(), vec![Action::Continue]);
+
+    round.apply(Action::Continue).unwrap();
+    assert_eq!(round.state, State::KoiKoi);
+    assert_eq!(round.legal_actions(), vec![Action::KoiKoi(true), Action::KoiKoi(false)]);
+    assert_eq!(round.yaku_points(Player::First), 5);
+
+    let mut stopped = round.clone();
+    stopped.apply(Action::KoiKoi(false)).unwrap();
+    assert_eq!(stopped.state, State::RoundOver);
+    assert!(stopped.legal_actions().is_empty());
+    assert_eq!(stopped.round_points(Player::First), Some(5));
+    assert_eq!(stopped.round_points(Player::Second), Some(-5));
+
+    round.apply(Action::KoiKoi(true)).unwrap();
+    assert_eq!((round.state, round.turn_16, round.turn_player()), (State::Discard, 2, Player::Second));
+    assert_eq!(round.round_points(Player::First), None);
+
+    // a discard pairing with no field card goes to the field
+    round.apply(Action::Discard(Card::new(2,1))).unwrap();
+    assert_eq!(round.state, State::DiscardPick);
+    assert_eq!(round.legal_actions(), vec![Action::Continue]);
+    round.apply(Action::Continue).unwrap();
+    assert!(round.field.contains(Card::new(2,1)));
+    assert_eq!(round.legal_actions(), vec![Action::Draw]);
+}
+
+#[test]
+fn rejected_actions() {
+    let (wisteria, pine) = (Card::new(4,1), Card::new(2,1));
+    let mut round = round();
+    reject(&mut round, Action::Draw, GameError::WrongPhase(State::Discard));
+    reject(&mut round, Action::Continue, GameError::WrongPhase(State::Discard));
+    reject(&mut round, Action::Discard(pine), GameError::CardNotInHand(pine));
+
+    round.apply(Action::Discard(wisteria)).unwrap();
+    reject(&mut round, Action::Discard(Card::new(5,1)), GameError::WrongPhase(State::DiscardPick));
+    reject(&mut round, Action::Continue, GameError::ChoiceRequired);
+    reject(&mut round, Action::Pick(Card::new(8,2)), GameError::CardNotPairable(Card::new(8,2)));
+
+    round.apply(Action::Pick(Card::new(4,2))).unwrap();
+    let mut empty = round.clone();
+    empty.stock.clear();
+    reject(&mut empty, Action::Draw, GameError::EmptyStock);
+
+    round.apply(Action::Draw).unwrap();
+    reject(&mut round, Action::Pick(Card::new(8,2)), GameError::UnexpectedChoice);
+    round.apply(Action::Continue).unwrap();
+    reject(&mut round, Action::Continue, GameError::ChoiceRequired);
+
+    round.apply(Action::KoiKoi(false)).unwrap();
+    reject(&mut round, Action::Discard(Card::new(5,1)), GameError::RoundOver);
+    reject(&mut round, Action::KoiKoi(true), GameError::RoundOver);
+    reject(&mut round, Action::Continue, GameError::RoundOver);
+}
+
+#[test]
+fn finished_games_reject_actions() {
+    let rules = RuleSet { round_total: 1, ..RuleSet::default() };
+    let mut game = GameState::new(GameConfig { rules, ..GameConfig::default() });
+    while !game.game_over {
+        game.step(game.legal_actions()[0]).unwrap();
+    }
+    let points = game.points;
+    assert_eq!(game.step(Action::Draw), Err(GameError::GameOver));
+    assert_eq!(game.points, points);
+}
+
+/// Plays the winning round of [`round`] as the first round of `game`.
+fn play_winning_round(game: &mut GameState) {
+    game.round_state = round();
+    for action in [Action::Discard(Card::new(4,1)), Action::Pick(Card::new(4,3)), Action::Draw, Action::Continue, Action::KoiKoi(false)] {
+        game.step(action).unwrap();
+    }
+}
+
+#[test]
+fn rounds_transfer_their_points() {
+    let mut dealers_lost = 0;
+    for seed in 0..20 {
+        let mut game = GameState::new(GameConfig { seed, ..GameConfig::default() });
+        let mut step = 0;
+        while !game.game_over {
+            let actions = game.legal_actions();
+            let action = actions[step % actions.len()];
+            let mut finished = game.round_state.clone();
+            let (round, points) = (game.round, game.points);
+            game.step(action).unwrap();
+            if game.round != round || game.game_over {
+                finished.apply(action).unwrap();
+                let won = Player::BOTH.map(|player| finished.round_points(player).unwrap());
+                assert_eq!(won[0], -won[1]);
+                assert_eq!(game.points, [points[0] + won[0], points[1] + won[1]]);
+                let winner = if won[0] > 0 { Player::First } else { Player::Second };
+                dealers_lost += (winner != finished.dealer) as usize;
+                if !game.game_over {
+                    // the winner of a round deals the next one
+                    assert_eq!(game.round_state.dealer, winner);
+                }
+            }
+            step += 1;
+        }
+        assert!(game.round == 8 || game.points.iter().any(|&point| point <= 0));
+    }
+    assert!(dealers_lost > 0);
+}
+
+#[test]
+fn games_end_without_points() {
+    let mut game = GameState::new(GameConfig { init_point: 5, ..GameConfig::default() });
+    play_winning_round(&mut game);
+    assert!(game.game_over);
+    assert_eq!(game.points, [10, 0]);
+    assert_eq!(game.winner, Some(Player::First));
+    assert_eq!(game.round, 1);
+
+    let mut game = GameState::new(GameConfig::default());
+    play_winning_round(&mut game);
+    assert!(!game.game_over);
+    assert_eq!((game.round, game.round_state.dealer), (2, Player::First));
+
+    let rules = RuleSet { round_total: 0, ..RuleSet::default() };
+    let mut game = GameState::new(GameConfig { rules, ..GameConfig::default() });
+    play_winning_round(&mut game);
+    assert!(game.game_over);
+}
+
+#[test]
+fn card_log_rows() {
+    let logged = |round: &RoundState, turn: usize, row: usize| -> CardSet {
+        let values = &round.card_log[turn - 1][row];
+        assert!(values.iter().all(|&x| x == 0. || x == 1.));
+        Card::all().filter(|card| values[card.index()] == 1.).collect()
+    };
+    let set = |pairs: &[(u8, u8)]| CardSet::of(&cards(pairs));
+    let mut round = round();
+    assert!(round.card_log.iter().flatten().flatten().all(|&x| x == 0.));
+
+    round.apply(Action::Discard(Card::new(4,1))).unwrap();
+    assert_eq!(logged(&round, 1, LOG_DISCARD), set(&[(4,1)]));
+    assert_eq!(logged(&round, 1, LOG_DISCARD + LOG_PAIRING), set(&[(4,2), (4,3)]));
+    assert!(logged(&round, 1, LOG_DISCARD + LOG_PICK).is_empty());
+
+    round.apply(Action::Pick(Card::new(4,3))).unwrap();
+    assert_eq!(logged(&round, 1, LOG_DISCARD + LOG_PICK), set(&[(4,3)]));
+    assert_eq!(logged(&round, 1, LOG_DISCARD + LOG_COLLECT), set(&[(4,3)]));
+
+    round.apply(Action::Draw).unwrap();
+    assert_eq!(logged(&round, 1, LOG_DRAW), set(&[(8,1)]));
+    assert_eq!(logged(&round, 1, LOG_DRAW + LOG_PAIRING), set(&[(8,2)]));
+
+    // a single pairing card is collected without a pick
+    round.apply(Action::Continue).unwrap();
+    assert!(logged(&round, 1, LOG_DRAW + LOG_PICK).is_empty());
+    assert_eq!(logged(&round, 1, LOG_DRAW + LOG_COLLECT), set(&[(8,2)]));
+    assert!(round.card_log[1..].iter().flatten().flatten().all(|&x| x == 0.));
+
+    round.apply(Action::KoiKoi(true)).unwrap();
+    round.apply(Action::Discard(Card::new(2,1))).unwrap();
+    round.apply(Action::Continue).unwrap();
+    assert_eq!(logged(&round, 2, LOG_DISCARD), set(&[(2,1)]));
+    assert!(logged(&round, 2, LOG_DISCARD + LOG_PAIRING).is_empty());
+    assert!(logged(&round, 2, LOG_DISCARD + LOG_COLLECT).is_empty());
+}
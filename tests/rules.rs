@@ -0,0 +1,37 @@
+use std::{env, fs};
+use rust_burn_test::game::Player;
+use rust_burn_test::record::GameRecord;
+use rust_burn_test::rules::{KoiKoiBonus, RuleSet};
+
+#[test]
+fn rule_files() {
+    let dir = env::temp_dir();
+    let toml = dir.join(format!("koikoi-rules-{}.toml", std::process::id()));
+    fs::write(&toml, "round_total = 12\nviewing_points = [5, 5]\nkoikoi_bonus = \"doubling\"\n").unwrap();
+    let json = dir.join(format!("koikoi-rules-{}.json", std::process::id()));
+    fs::write(&json, r#"{"viewing_yaku": false, "koikoi_bonus": "none"}"#).unwrap();
+    let (from_toml, from_json) = (RuleSet::load(&toml), RuleSet::load(&json));
+    fs::remove_file(&toml).unwrap();
+    fs::remove_file(&json).unwrap();
+
+    let expected = RuleSet {
+        round_total: 12,
+        viewing_points: [5, 5],
+        koikoi_bonus: KoiKoiBonus::Doubling,
+        ..RuleSet::default()
+    };
+    assert_eq!(from_toml.unwrap(), expected);
+    let expected = RuleSet { viewing_yaku: false, koikoi_bonus: KoiKoiBonus::None, ..RuleSet::default() };
+    assert_eq!(from_json.unwrap(), expected);
+    assert!(RuleSet::load(&dir.join("koikoi-no-such-rules.toml")).is_err());
+}
+
+#[test]
+fn records_without_rules() {
+    let line = r#"{"seed":3,"dealer":1,"round_total":4,"init_point":30,"player_name":["a","b"],"actions":[]}"#;
+    let record: GameRecord = serde_json::from_str(line).unwrap();
+    assert_eq!(record.dealer, Player::Second);
+    assert_eq!(record.rules, RuleSet { round_total: 4, ..RuleSet::default() });
+    let written = serde_json::to_string(&record).unwrap();
+    assert_eq!(serde_json::from_str::<GameRecord>(&written).unwrap(), record);
+}
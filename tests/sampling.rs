@@ -0,0 +1,54 @@
+use rust_burn_test::agent::Sampling;
+use rust_burn_test::game::{Action, Card};
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn argmax_takes_the_best_action() {
+    assert_eq!(Sampling::Argmax.probabilities(&[0.5, 2., -1.]), [0., 1., 0.]);
+    assert!(Sampling::Argmax.probabilities(&[]).is_empty());
+}
+
+#[test]
+fn temperatures_soften_the_logits() {
+    let logits = [0., 2f32.ln(), 3f32.ln()];
+    assert_close(&Sampling::Temperature(1.).probabilities(&logits), &[1. / 6., 2. / 6., 3. / 6.]);
+    let logits = [0., 4f32.ln()];
+    assert_close(&Sampling::Temperature(2.).probabilities(&logits), &[1. / 3., 2. / 3.]);
+    for temperature in [0., -1., f32::NAN] {
+        assert_eq!(Sampling::Temperature(temperature).probabilities(&logits), [0., 1.]);
+        assert_eq!(Sampling::TopK(2, temperature).probabilities(&logits), [0., 1.]);
+    }
+}
+
+#[test]
+fn top_k_keeps_the_best_actions() {
+    let logits = [3f32.ln(), 0., 1f32.ln() - 1., 2f32.ln()];
+    assert_close(&Sampling::TopK(2, 1.).probabilities(&logits), &[0.6, 0., 0., 0.4]);
+    assert_eq!(Sampling::TopK(0, 1.).probabilities(&logits), [1., 0., 0., 0.]);
+    assert_close(&Sampling::TopK(9, 1.).probabilities(&[0., 0.]), &[0.5, 0.5]);
+}
+
+#[test]
+fn illegal_actions_are_masked() {
+    let mut logits = [0.; 48];
+    logits[Card::new(1,1).index()] = 10.;
+    logits[Card::new(2,1).index()] = 1.;
+    logits[Card::new(3,1).index()] = 2.;
+    let legal = vec![Action::Discard(Card::new(2,1)), Action::Discard(Card::new(3,1))];
+    for sampling in [Sampling::Argmax, Sampling::Temperature(0.), Sampling::TopK(1, 1.)] {
+        assert_eq!(sampling.policy(&logits, legal.clone()), [(legal[0], 0.), (legal[1], 1.)]);
+    }
+    let policy = Sampling::Temperature(1.).policy(&logits, legal.clone());
+    let e = std::f32::consts::E;
+    assert_close(&policy.iter().map(|(_, p)| *p).collect::<Vec<_>>(), &[1. / (1. + e), e / (1. + e)]);
+    assert_eq!(policy.iter().map(|(action, _)| *action).collect::<Vec<_>>(), legal);
+
+    let policy = Sampling::Argmax.policy(&[0.3, 0.7], vec![Action::KoiKoi(true), Action::KoiKoi(false)]);
+    assert_eq!(policy, [(Action::KoiKoi(true), 1.), (Action::KoiKoi(false), 0.)]);
+}
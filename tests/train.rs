@@ -0,0 +1,57 @@
+use burn::backend::{ndarray::NdArrayDevice, NdArray};
+use burn::data::{dataloader::batcher::Batcher, dataset::Dataset};
+use burn::tensor::{Tensor, TensorData};
+use rust_burn_test::agent::Decision;
+use rust_burn_test::arena::{Arena, GreedyAgent, RandomAgent};
+use rust_burn_test::game::GameConfig;
+use rust_burn_test::game_tensor::feature_array;
+use rust_burn_test::rules::RuleSet;
+use rust_burn_test::train::{masked_classification, DecisionBatcher, DecisionDataset};
+
+#[test]
+fn dataset_replays_the_decisions() {
+    let config = GameConfig { rules: RuleSet { round_total: 1, ..RuleSet::default() }, ..GameConfig::default() };
+    let (_, records) = Arena::new(2, config).play_recorded(&mut GreedyAgent, &mut RandomAgent::new(0));
+    let dataset = DecisionDataset::new(&records, Decision::Discard).unwrap();
+    assert!(dataset.len() > 0);
+    for item in dataset.iter() {
+        assert_eq!(item.features.len(), 300 * 48);
+        assert_eq!(item.legal.len(), 48);
+        assert!(item.legal[item.target]);
+        assert!(item.legal.iter().filter(|&&legal| legal).count() >= 2);
+    }
+
+    // the items are the positions replayed from the records
+    let record = &records[0];
+    let step = (0..record.actions.len())
+        .find(|&step| Decision::of(&record.state_at(step).unwrap().round_state) == Some(Decision::Discard))
+        .unwrap();
+    let game = record.state_at(step).unwrap();
+    let first = dataset.get(0).unwrap();
+    assert_eq!(first.features, feature_array(&game.observation(game.round_state.turn_player())).iter().copied().collect::<Vec<_>>());
+    assert_eq!(Some(first.target), Decision::index(record.actions[step].action));
+
+    let items: Vec<_> = dataset.iter().take(3).collect();
+    let batch = DecisionBatcher::<NdArray>::new(NdArrayDevice::default()).batch(items);
+    assert_eq!(batch.features.dims(), [3, 300, 48]);
+    assert_eq!(batch.legal.dims(), [3, 48]);
+}
+
+#[test]
+fn illegal_logits_are_masked() {
+    let device = NdArrayDevice::default();
+    let (_, records) = Arena::new(2, GameConfig::default()).play_recorded(&mut GreedyAgent, &mut GreedyAgent);
+    let item = DecisionDataset::new(&records, Decision::KoiKoi).unwrap().get(0).unwrap();
+    let mut batch = DecisionBatcher::<NdArray>::new(device).batch(vec![item]);
+    batch.legal = Tensor::from_data(TensorData::new(vec![true, true], [1, 2]), &device);
+
+    let logits = Tensor::<NdArray, 2>::from_data([[0., 0.]], &device);
+    let loss: f32 = masked_classification(logits, batch.clone()).loss.into_scalar();
+    assert!((loss - 2f32.ln()).abs() < 1e-5);
+
+    batch.legal = Tensor::from_data(TensorData::new(vec![false, true], [1, 2]), &device);
+    batch.targets = Tensor::from_data([1], &device);
+    let logits = Tensor::<NdArray, 2>::from_data([[10., 0.]], &device);
+    let loss: f32 = masked_classification(logits, batch).loss.into_scalar();
+    assert!(loss.abs() < 1e-5);
+}
@@ -0,0 +1,208 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rust_burn_test::game::{Card, CardSet, Player, RoundState};
+use rust_burn_test::rules::{KoiKoiBonus, RuleSet};
+
+fn cards(cards: &[(u8, u8)]) -> CardSet {
+    cards.iter().map(|&(month, rank)| Card::new(month, rank)).collect()
+}
+
+fn round(pile: &[(u8, u8)], koikoi_num: usize) -> RoundState {
+    round_with(RuleSet::default(), pile, koikoi_num)
+}
+
+fn round_with(rules: RuleSet, pile: &[(u8, u8)], koikoi_num: usize) -> RoundState {
+    let mut round = RoundState::new(Player::First, rules, &mut StdRng::seed_from_u64(0));
+    round.pile[Player::First] = cards(pile);
+    for turn in 0..koikoi_num {
+        round.koikoi[Player::First][turn] = 1;
+    }
+    round
+}
+
+struct Case {
+    pile: &'static [(u8, u8)],
+    koikoi_num: usize,
+    yaku: &'static [(&'static str, i32)],
+    points: i32,
+}
+
+const CASES: &[Case] = &[
+    Case { pile: &[], koikoi_num: 0, yaku: &[], points: 0 },
+    Case {
+        pile: &[(1,1), (3,1), (8,1), (11,1), (12,1)],
+        koikoi_num: 0,
+        yaku: &[("Five Lights", 10)],
+        points: 10,
+    },
+    Case {
+        pile: &[(1,1), (3,1), (8,1), (12,1)],
+        koikoi_num: 0,
+        yaku: &[("Four Lights", 8)],
+        points: 8,
+    },
+    Case {
+        pile: &[(1,1), (3,1), (11,1), (12,1)],
+        koikoi_num: 0,
+        yaku: &[("Rainy Four Lights", 7)],
+        points: 7,
+    },
+    Case {
+        pile: &[(1,1), (3,1), (12,1)],
+        koikoi_num: 0,
+        yaku: &[("Three Lights", 5)],
+        points: 5,
+    },
+    Case {
+        pile: &[(1,1), (11,1), (12,1)],
+        koikoi_num: 0,
+        yaku: &[],
+        points: 0,
+    },
+    Case {
+        pile: &[(6,1), (7,1), (10,1)],
+        koikoi_num: 0,
+        yaku: &[("Boar-Deer-Butterfly", 5)],
+        points: 5,
+    },
+    Case {
+        pile: &[(3,1), (9,1)],
+        koikoi_num: 0,
+        yaku: &[("Flower Viewing Sake", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(3,1), (9,1)],
+        koikoi_num: 1,
+        yaku: &[("Flower Viewing Sake", 3), ("Koi-Koi", 1)],
+        points: 4,
+    },
+    Case {
+        pile: &[(8,1), (9,1)],
+        koikoi_num: 0,
+        yaku: &[("Moon Viewing Sake", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(8,1), (9,1)],
+        koikoi_num: 2,
+        yaku: &[("Moon Viewing Sake", 3), ("Koi-Koi", 2)],
+        points: 5,
+    },
+    Case {
+        pile: &[(2,1), (4,1), (5,1), (8,2), (11,2)],
+        koikoi_num: 0,
+        yaku: &[("Tane", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(2,1), (4,1), (5,1), (6,1), (8,2), (11,2)],
+        koikoi_num: 0,
+        yaku: &[("Tane", 2)],
+        points: 2,
+    },
+    Case {
+        pile: &[(1,2), (2,2), (3,2)],
+        koikoi_num: 0,
+        yaku: &[("Red Ribbons", 5)],
+        points: 5,
+    },
+    Case {
+        pile: &[(6,2), (9,2), (10,2)],
+        koikoi_num: 0,
+        yaku: &[("Blue Ribbons", 5)],
+        points: 5,
+    },
+    Case {
+        pile: &[(1,2), (2,2), (3,2), (6,2), (9,2), (10,2)],
+        koikoi_num: 0,
+        yaku: &[("Red & Blue Ribbons", 10), ("Tan", 2)],
+        points: 12,
+    },
+    Case {
+        pile: &[(1,2), (4,2), (5,2), (7,2), (11,3)],
+        koikoi_num: 0,
+        yaku: &[("Tan", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(1,3), (1,4), (2,3), (2,4), (3,3), (3,4), (4,3), (4,4), (5,3), (5,4)],
+        koikoi_num: 0,
+        yaku: &[("Kasu", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(1,3), (1,4), (2,3), (2,4), (3,3), (3,4), (4,3), (4,4), (5,3), (9,1)],
+        koikoi_num: 0,
+        yaku: &[("Kasu", 1)],
+        points: 1,
+    },
+    Case {
+        pile: &[(1,1), (3,1), (12,1), (3,2), (9,1)],
+        koikoi_num: 0,
+        yaku: &[("Three Lights", 5), ("Flower Viewing Sake", 1)],
+        points: 6,
+    },
+    Case {
+        pile: &[(6,1), (7,1), (10,1)],
+        koikoi_num: 3,
+        yaku: &[("Boar-Deer-Butterfly", 5), ("Koi-Koi", 3)],
+        points: 8,
+    },
+    Case {
+        pile: &[(6,1), (7,1), (10,1)],
+        koikoi_num: 4,
+        yaku: &[("Boar-Deer-Butterfly", 5), ("Koi-Koi", 4)],
+        points: 10,
+    },
+    Case {
+        pile: &[(6,1), (7,1), (10,1)],
+        koikoi_num: 6,
+        yaku: &[("Boar-Deer-Butterfly", 5), ("Koi-Koi", 6)],
+        points: 20,
+    },
+];
+
+#[test]
+fn yaku_table() {
+    for case in CASES {
+        let round = round(case.pile, case.koikoi_num);
+        let yaku: Vec<_> = round.yaku(Player::First).iter().map(|&(_, name, point)| (name, point)).collect();
+        assert_eq!(yaku, case.yaku, "yaku of {:?}", case.pile);
+        assert_eq!(round.yaku_points(Player::First), case.points, "points of {:?}", case.pile);
+    }
+}
+
+#[test]
+fn yaku_points_are_per_player() {
+    let mut round = round(&[(1,1), (3,1), (8,1), (12,1)], 1);
+    round.pile[Player::Second] = cards(&[(1,2), (2,2), (3,2)]);
+    assert_eq!(round.yaku_points(Player::First), 9);
+    assert_eq!(round.yaku_points(Player::Second), 5);
+}
+
+#[test]
+fn house_rules() {
+    let sake = &[(3,1), (9,1), (8,1)];
+    let no_viewing = RuleSet { viewing_yaku: false, ..RuleSet::default() };
+    assert_eq!(round_with(no_viewing, sake, 0).yaku_points(Player::First), 0);
+    // doubled from 7 points, and again once the opponent called koi-koi
+    let nintendo = RuleSet::nintendo();
+    let mut state = round_with(nintendo, sake, 0);
+    assert_eq!(state.yaku_points(Player::First), 20);
+    state.koikoi[Player::Second][0] = 1;
+    assert_eq!(state.yaku_points(Player::First), 40);
+    let mut state = round_with(nintendo, &[(1,1), (3,1), (12,1)], 0);
+    assert_eq!(state.yaku_points(Player::First), 5);
+    state.koikoi[Player::First][0] = 1;
+    assert_eq!(state.yaku_points(Player::First), 5);
+
+    let dross = &[(1,3), (1,4), (2,3), (2,4), (3,3), (3,4), (4,3), (4,4), (5,3), (9,1)];
+    assert_eq!(round(dross, 0).yaku_points(Player::First), 1);
+    let no_dual_use = RuleSet { sake_cup_chaff: false, ..RuleSet::default() };
+    assert_eq!(round_with(no_dual_use, dross, 0).yaku_points(Player::First), 0);
+
+    let no_bonus = RuleSet { koikoi_bonus: KoiKoiBonus::None, ..RuleSet::default() };
+    let lights = &[(1,1), (3,1), (12,1)];
+    assert_eq!(round(lights, 2).yaku_points(Player::First), 7);
+    assert_eq!(round_with(no_bonus, lights, 2).yaku_points(Player::First), 5);
+}